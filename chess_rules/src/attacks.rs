@@ -0,0 +1,129 @@
+//! The squares each kind of piece attacks from every square, worked out at compile time.
+//! Sliding pieces use rays that are cut short at the first piece in the way
+
+use crate::{Bitboard, PieceColor, Square};
+
+const KNIGHT_OFFSETS: [(i8, i8); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
+// Also the directions sliding pieces move in, in this order: N, NE, E, SE, S, SW, W, NW
+const KING_OFFSETS: [(i8, i8); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+const ROOK_DIRECTIONS: [usize; 4] = [0, 2, 4, 6];
+const BISHOP_DIRECTIONS: [usize; 4] = [1, 3, 5, 7];
+
+/// The squares one step away from each square by any of the offsets
+const fn step_table(offsets: &[(i8, i8)]) -> [u64; 64] {
+    let mut table = [0; 64];
+    let mut square = 0;
+    while square < 64 {
+        let mut i = 0;
+        while i < offsets.len() {
+            let file = (square % 8) as i8 + offsets[i].0;
+            let rank = (square / 8) as i8 + offsets[i].1;
+            if file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                table[square] |= 1 << (rank * 8 + file);
+            }
+            i += 1;
+        }
+        square += 1;
+    }
+    table
+}
+
+/// The squares from each square to the edge of the board in every direction
+const fn ray_tables() -> [[u64; 64]; 8] {
+    let mut tables = [[0; 64]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (file_delta, rank_delta) = KING_OFFSETS[direction];
+        let mut square = 0;
+        while square < 64 {
+            let mut file = (square % 8) as i8 + file_delta;
+            let mut rank = (square / 8) as i8 + rank_delta;
+            while file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+                tables[direction][square] |= 1 << (rank * 8 + file);
+                file += file_delta;
+                rank += rank_delta;
+            }
+            square += 1;
+        }
+        direction += 1;
+    }
+    tables
+}
+
+const KNIGHT_ATTACKS: [u64; 64] = step_table(&KNIGHT_OFFSETS);
+const KING_ATTACKS: [u64; 64] = step_table(&KING_OFFSETS);
+const WHITE_PAWN_ATTACKS: [u64; 64] = step_table(&[(-1, 1), (1, 1)]);
+const BLACK_PAWN_ATTACKS: [u64; 64] = step_table(&[(-1, -1), (1, -1)]);
+const RAYS: [[u64; 64]; 8] = ray_tables();
+
+pub(crate) fn knight_attacks(square: Square) -> Bitboard {
+    Bitboard(KNIGHT_ATTACKS[square.index()])
+}
+
+pub(crate) fn king_attacks(square: Square) -> Bitboard {
+    Bitboard(KING_ATTACKS[square.index()])
+}
+
+/// The squares a pawn of the color attacks diagonally, whether or not there's anything to take
+pub(crate) fn pawn_attacks(color: PieceColor, square: Square) -> Bitboard {
+    match color {
+        PieceColor::White => Bitboard(WHITE_PAWN_ATTACKS[square.index()]),
+        PieceColor::Black => Bitboard(BLACK_PAWN_ATTACKS[square.index()]),
+    }
+}
+
+/// The squares along the ray up to and including the first occupied one
+fn ray_attacks(square: Square, direction: usize, occupied: Bitboard) -> Bitboard {
+    let ray = RAYS[direction][square.index()];
+    let blockers = ray & occupied.0;
+    if blockers == 0 {
+        return Bitboard(ray);
+    }
+    // Rays going up the board meet their nearest blocker at its lowest bit,
+    // and the others at its highest
+    let (file_delta, rank_delta) = KING_OFFSETS[direction];
+    let nearest = if rank_delta > 0 || (rank_delta == 0 && file_delta > 0) {
+        blockers.trailing_zeros()
+    } else {
+        63 - blockers.leading_zeros()
+    };
+    Bitboard(ray ^ RAYS[direction][nearest as usize])
+}
+
+pub(crate) fn rook_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    ROOK_DIRECTIONS
+        .iter()
+        .fold(Bitboard::EMPTY, |attacks, &direction| {
+            attacks | ray_attacks(square, direction, occupied)
+        })
+}
+
+pub(crate) fn bishop_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    BISHOP_DIRECTIONS
+        .iter()
+        .fold(Bitboard::EMPTY, |attacks, &direction| {
+            attacks | ray_attacks(square, direction, occupied)
+        })
+}
+
+pub(crate) fn queen_attacks(square: Square, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
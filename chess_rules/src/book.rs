@@ -0,0 +1,154 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use crate::{polyglot_key, Move, PieceType, Position, Square};
+
+// Each entry is a key, a move, a weight and a learning field, all big-endian
+const ENTRY_SIZE: usize = 16;
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(io::Error),
+    /// The file isn't made of whole 16-byte entries
+    Truncated,
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(err) => write!(f, "could not read the book: {}", err),
+            BookError::Truncated => write!(f, "the book ends in the middle of an entry"),
+        }
+    }
+}
+
+impl Error for BookError {}
+
+impl From<io::Error> for BookError {
+    fn from(err: io::Error) -> Self {
+        BookError::Io(err)
+    }
+}
+
+/// One move of a Polyglot book for the position with the key
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BookEntry {
+    pub key: u64,
+    /// The move packed into 16 bits: the target square, the origin square and the promotion
+    pub raw_move: u16,
+    /// How good the move is compared to the others for the same position
+    pub weight: u16,
+    pub learn: u32,
+}
+
+impl BookEntry {
+    /// The move in the position, or None if it isn't legal there. Polyglot writes castling
+    /// as the king taking its rook, which becomes the king moving two squares in standard chess
+    pub fn to_move(&self, position: &Position) -> Option<Move> {
+        let square = |bits: u16| Square::from_index((bits & 0x3f) as usize);
+        let promotion = match (self.raw_move >> 12) & 0x7 {
+            0 => None,
+            1 => Some(PieceType::Knight),
+            2 => Some(PieceType::Bishop),
+            3 => Some(PieceType::Rook),
+            4 => Some(PieceType::Queen),
+            _ => return None,
+        };
+        let mut mv = Move {
+            from: square(self.raw_move >> 6),
+            to: square(self.raw_move),
+            promotion,
+        };
+        if !position.is_chess960() && position.castling_rook(mv).is_some() {
+            mv.to = position.landing_square(mv);
+        }
+        if position.is_legal(mv) {
+            Some(mv)
+        } else {
+            None
+        }
+    }
+}
+
+/// An opening book in the Polyglot format, with its entries sorted by key
+/// so that the moves for a position are found with a binary search
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+impl Book {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BookError> {
+        Book::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::Truncated);
+        }
+        let entries = bytes
+            .chunks(ENTRY_SIZE)
+            .map(|chunk| {
+                let mut key = [0; 8];
+                key.copy_from_slice(&chunk[0..8]);
+                let mut learn = [0; 4];
+                learn.copy_from_slice(&chunk[12..16]);
+                BookEntry {
+                    key: u64::from_be_bytes(key),
+                    raw_move: u16::from_be_bytes([chunk[8], chunk[9]]),
+                    weight: u16::from_be_bytes([chunk[10], chunk[11]]),
+                    learn: u32::from_be_bytes(learn),
+                }
+            })
+            .collect();
+        Ok(Book { entries })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entry for the position with the key, in the order of the book
+    pub fn entries(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// Picks one of the entries for the key, each as likely as its weight allows, using
+    /// `random`, which should be spread evenly over all the values of a u64.
+    /// Moves with no weight are never picked
+    pub fn choose(&self, key: u64, random: u64) -> Option<&BookEntry> {
+        let entries = self.entries(key);
+        let total: u64 = entries.iter().map(|entry| entry.weight as u64).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut pick = random % total;
+        entries.iter().find(|entry| {
+            let weight = entry.weight as u64;
+            if pick < weight {
+                true
+            } else {
+                pick -= weight;
+                false
+            }
+        })
+    }
+
+    /// The book's moves in the position, with their weights, leaving out any that aren't legal
+    pub fn moves(&self, position: &Position) -> Vec<(Move, u16)> {
+        self.entries(polyglot_key(position))
+            .iter()
+            .filter_map(|entry| Some((entry.to_move(position)?, entry.weight)))
+            .collect()
+    }
+
+    /// Picks one of the book's moves in the position like [`choose`](Book::choose) does
+    pub fn choose_move(&self, position: &Position, random: u64) -> Option<Move> {
+        self.choose(polyglot_key(position), random)?
+            .to_move(position)
+    }
+}
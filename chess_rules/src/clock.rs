@@ -0,0 +1,266 @@
+use std::{error::Error, fmt, str::FromStr, time::Duration};
+
+use crate::PieceColor;
+
+/// One stage of a time control: a number of moves to play in some time,
+/// or the rest of the game if there's no number of moves
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimePeriod {
+    pub moves: Option<u32>,
+    pub time: Duration,
+}
+
+/// Time given to a player for each move they make
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TimeBonus {
+    None,
+    /// Fischer increment: added after every move
+    Increment(Duration),
+    /// Bronstein delay: the time used for the move is given back, up to the delay
+    Bronstein(Duration),
+    /// Simple delay: the clock only starts running once the delay has passed
+    Delay(Duration),
+}
+
+/// How much time each player gets. Written like the PGN TimeControl tag, with periods
+/// separated by colons and the bonus at the end: `300` is five minutes sudden death,
+/// `180+2` adds two seconds per move, `40/7200:3600` is 40 moves in two hours and
+/// then an hour for the rest of the game. `300d5` and `300b5` use a simple or a
+/// Bronstein delay of five seconds instead of an increment
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TimeControl {
+    /// The last period repeats if it has a number of moves
+    pub periods: Vec<TimePeriod>,
+    pub bonus: TimeBonus,
+}
+
+impl TimeControl {
+    pub fn sudden_death(time: Duration) -> Self {
+        TimeControl {
+            periods: vec![TimePeriod { moves: None, time }],
+            bonus: TimeBonus::None,
+        }
+    }
+
+    pub fn fischer(time: Duration, increment: Duration) -> Self {
+        TimeControl {
+            bonus: TimeBonus::Increment(increment),
+            ..Self::sudden_death(time)
+        }
+    }
+
+    pub fn bronstein(time: Duration, delay: Duration) -> Self {
+        TimeControl {
+            bonus: TimeBonus::Bronstein(delay),
+            ..Self::sudden_death(time)
+        }
+    }
+
+    pub fn simple_delay(time: Duration, delay: Duration) -> Self {
+        TimeControl {
+            bonus: TimeBonus::Delay(delay),
+            ..Self::sudden_death(time)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TimeControlError(pub String);
+
+impl fmt::Display for TimeControlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid time control: {}", self.0)
+    }
+}
+
+impl Error for TimeControlError {}
+
+/// Fails on negative times, and on times too long for a `Duration`
+fn parse_seconds(s: &str) -> Option<Duration> {
+    Duration::try_from_secs_f64(s.parse().ok()?).ok()
+}
+
+impl FromStr for TimeControl {
+    type Err = TimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || TimeControlError(s.to_string());
+
+        let (periods, bonus) = match s.find(&['+', 'd', 'b'][..]) {
+            Some(i) => {
+                let delay = parse_seconds(&s[i + 1..]).ok_or_else(error)?;
+                let bonus = match &s[i..=i] {
+                    "+" => TimeBonus::Increment(delay),
+                    "d" => TimeBonus::Delay(delay),
+                    _ => TimeBonus::Bronstein(delay),
+                };
+                (&s[..i], bonus)
+            }
+            None => (s, TimeBonus::None),
+        };
+
+        let periods = periods
+            .split(':')
+            .map(|period| match period.split_once('/') {
+                Some((moves, time)) => Some(TimePeriod {
+                    moves: Some(moves.parse().ok().filter(|&moves| moves > 0)?),
+                    time: parse_seconds(time)?,
+                }),
+                None => Some(TimePeriod {
+                    moves: None,
+                    time: parse_seconds(period)?,
+                }),
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(error)?;
+        // Only the last period can last for the rest of the game
+        if periods[..periods.len() - 1]
+            .iter()
+            .any(|period| period.moves.is_none())
+        {
+            return Err(error());
+        }
+        Ok(TimeControl { periods, bonus })
+    }
+}
+
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, period) in self.periods.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            if let Some(moves) = period.moves {
+                write!(f, "{}/", moves)?;
+            }
+            write!(f, "{}", period.time.as_secs_f64())?;
+        }
+        match self.bonus {
+            TimeBonus::None => Ok(()),
+            TimeBonus::Increment(time) => write!(f, "+{}", time.as_secs_f64()),
+            TimeBonus::Delay(time) => write!(f, "d{}", time.as_secs_f64()),
+            TimeBonus::Bronstein(time) => write!(f, "b{}", time.as_secs_f64()),
+        }
+    }
+}
+
+fn index(color: PieceColor) -> usize {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    }
+}
+
+/// A chess clock: the time each player has left, counting down for the side to move
+#[derive(Clone, Debug)]
+pub struct Clock {
+    control: TimeControl,
+    // For the side to move, what was left when its turn started
+    remaining: [Duration; 2],
+    period: [usize; 2],
+    moves_in_period: [u32; 2],
+    turn: PieceColor,
+    turn_elapsed: Duration,
+    flagged: Option<PieceColor>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, turn: PieceColor) -> Self {
+        let time = control.periods[0].time;
+        Clock {
+            control,
+            remaining: [time; 2],
+            period: [0; 2],
+            moves_in_period: [0; 2],
+            turn,
+            turn_elapsed: Duration::default(),
+            flagged: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// The side whose clock is running
+    pub fn turn(&self) -> PieceColor {
+        self.turn
+    }
+
+    /// The time used so far this turn that counts against the clock
+    fn charged(&self) -> Duration {
+        match self.control.bonus {
+            TimeBonus::Delay(delay) => self.turn_elapsed.saturating_sub(delay),
+            _ => self.turn_elapsed,
+        }
+    }
+
+    pub fn remaining(&self, color: PieceColor) -> Duration {
+        let remaining = self.remaining[index(color)];
+        if color == self.turn {
+            remaining.saturating_sub(self.charged())
+        } else {
+            remaining
+        }
+    }
+
+    /// What's left of the simple delay before the clock of the side to move starts running
+    pub fn delay_left(&self) -> Duration {
+        match self.control.bonus {
+            TimeBonus::Delay(delay) => delay.saturating_sub(self.turn_elapsed),
+            _ => Duration::default(),
+        }
+    }
+
+    /// The side that ran out of time, if any. Its clock stops at zero
+    pub fn flagged(&self) -> Option<PieceColor> {
+        self.flagged
+    }
+
+    /// Lets time pass for the side to move
+    pub fn tick(&mut self, elapsed: Duration) {
+        if self.flagged.is_some() {
+            return;
+        }
+        self.turn_elapsed += elapsed;
+        if self.charged() >= self.remaining[index(self.turn)] {
+            self.flagged = Some(self.turn);
+        }
+    }
+
+    /// Stops the clock of the side to move once it has moved, adds its bonus,
+    /// and starts the other clock
+    pub fn press(&mut self) {
+        if self.flagged.is_some() {
+            return;
+        }
+        let i = index(self.turn);
+        let mut remaining = self.remaining(self.turn);
+        match self.control.bonus {
+            TimeBonus::Increment(increment) => remaining = remaining.saturating_add(increment),
+            TimeBonus::Bronstein(delay) => {
+                remaining = remaining.saturating_add(self.turn_elapsed.min(delay))
+            }
+            TimeBonus::None | TimeBonus::Delay(_) => {}
+        }
+
+        // Completing the moves of a period brings the time of the next one
+        self.moves_in_period[i] += 1;
+        if Some(self.moves_in_period[i]) == self.control.periods[self.period[i]].moves {
+            self.moves_in_period[i] = 0;
+            self.period[i] = (self.period[i] + 1).min(self.control.periods.len() - 1);
+            remaining = remaining.saturating_add(self.control.periods[self.period[i]].time);
+        }
+        self.remaining[i] = remaining;
+        self.turn_elapsed = Duration::default();
+        self.switch_to(self.turn.other());
+    }
+
+    /// Hands the clock to a side without anyone completing a move, like when one is taken back
+    pub fn switch_to(&mut self, color: PieceColor) {
+        let i = index(self.turn);
+        self.remaining[i] = self.remaining(self.turn);
+        self.turn = color;
+        self.turn_elapsed = Duration::default();
+    }
+}
@@ -0,0 +1,263 @@
+use std::{error::Error, fmt};
+
+use crate::{CastlingRights, Piece, PieceColor, PieceType, Position, Square};
+
+/// The standard starting position
+pub const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, PartialEq)]
+pub enum FenError {
+    MissingField,
+    InvalidPlacement(String),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidCounter(String),
+    /// Each side needs exactly one king
+    InvalidKings,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::MissingField => write!(f, "the FEN is missing fields"),
+            FenError::InvalidPlacement(s) => write!(f, "invalid piece placement: {}", s),
+            FenError::InvalidSideToMove(s) => write!(f, "invalid side to move: {}", s),
+            FenError::InvalidCastling(s) => write!(f, "invalid castling rights: {}", s),
+            FenError::InvalidEnPassant(s) => write!(f, "invalid en passant square: {}", s),
+            FenError::InvalidCounter(s) => write!(f, "invalid move counter: {}", s),
+            FenError::InvalidKings => write!(f, "each side must have exactly one king"),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+impl Position {
+    /// Parses a position in Forsyth-Edwards Notation. The move counters are optional
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut fields = fen.split_whitespace();
+        let mut next_field = || fields.next().ok_or(FenError::MissingField);
+        let mut position = Position::empty();
+
+        let placement = next_field()?;
+        let ranks: Vec<_> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::InvalidPlacement(placement.to_string()));
+        }
+        // Ranks are written from the eighth to the first
+        for (rank, row) in (0..8).rev().zip(ranks) {
+            let mut file = 0;
+            for c in row.chars() {
+                if let Some(empty) = c.to_digit(10) {
+                    // Checked before adding, so that long runs of digits can't overflow
+                    if !(1..=8).contains(&empty) || file + empty as u8 > 8 {
+                        return Err(FenError::InvalidPlacement(placement.to_string()));
+                    }
+                    file += empty as u8;
+                    continue;
+                }
+                let piece = Piece::from_char(c)
+                    .ok_or_else(|| FenError::InvalidPlacement(placement.to_string()))?;
+                if file >= 8 {
+                    return Err(FenError::InvalidPlacement(placement.to_string()));
+                }
+                position.set_piece_at(Square::new(file, rank), Some(piece));
+                file += 1;
+            }
+            if file != 8 {
+                return Err(FenError::InvalidPlacement(placement.to_string()));
+            }
+        }
+        for &color in [PieceColor::White, PieceColor::Black].iter() {
+            let king = Piece::new(color, PieceType::King);
+            if position
+                .pieces()
+                .filter(|(_, piece)| *piece == king)
+                .count()
+                != 1
+            {
+                return Err(FenError::InvalidKings);
+            }
+        }
+
+        let side_to_move = next_field()?;
+        position.set_side_to_move(match side_to_move {
+            "w" => PieceColor::White,
+            "b" => PieceColor::Black,
+            _ => return Err(FenError::InvalidSideToMove(side_to_move.to_string())),
+        });
+
+        let castling = next_field()?;
+        let mut castling_rights = CastlingRights::none();
+        // Shredder-FEN names the file of the rook, X-FEN only does when K or Q would be
+        // ambiguous because it's not the outermost rook on that side
+        let mut names_files = false;
+        if castling != "-" {
+            for c in castling.chars() {
+                let color = if c.is_ascii_uppercase() {
+                    PieceColor::White
+                } else {
+                    PieceColor::Black
+                };
+                let king = position.king_square(color).unwrap();
+                let rook = Piece::new(color, PieceType::Rook);
+                let is_rook = |file: u8| {
+                    position.piece_at(Square::new(file, color.back_rank())) == Some(rook)
+                };
+                let (king_side, rook_file) = match c.to_ascii_lowercase() {
+                    'k' => (true, (king.file() + 1..8).rev().find(|&file| is_rook(file))),
+                    'q' => (false, (0..king.file()).find(|&file| is_rook(file))),
+                    file @ 'a'..='h' => {
+                        names_files = true;
+                        let file = file as u8 - b'a';
+                        (file > king.file(), Some(file).filter(|&file| is_rook(file)))
+                    }
+                    _ => return Err(FenError::InvalidCastling(castling.to_string())),
+                };
+                // Rights that the king or rook couldn't use anyway are dropped
+                if king.rank() == color.back_rank() && rook_file.is_some() {
+                    castling_rights.set(color, king_side, rook_file);
+                }
+            }
+        }
+        position.set_castling_rights(castling_rights);
+        // Castling with the king off the e file, or a rook off the corners, is Chess960
+        let standard = [PieceColor::White, PieceColor::Black].iter().all(|&color| {
+            let king_file = position.king_square(color).unwrap().file();
+            [(true, 7), (false, 0)].iter().all(|&(king_side, corner)| {
+                match castling_rights.rook_file(color, king_side) {
+                    Some(file) => file == corner && king_file == 4,
+                    None => true,
+                }
+            })
+        });
+        position.set_chess960(names_files || !standard);
+
+        let en_passant = next_field()?;
+        position.set_en_passant(match en_passant {
+            "-" => None,
+            _ => {
+                let invalid = || FenError::InvalidEnPassant(en_passant.to_string());
+                let square = Square::from_algebraic(en_passant).ok_or_else(invalid)?;
+                // The square is the one the other side's pawn just skipped over, so that
+                // pawn has to be standing right in front of it
+                let side = position.side_to_move();
+                let skipped_rank = match side {
+                    PieceColor::White => 5,
+                    PieceColor::Black => 2,
+                };
+                let pawn = Piece::new(side.other(), PieceType::Pawn);
+                let pawn_square = square.offset(0, -side.forward());
+                if square.rank() != skipped_rank
+                    || pawn_square.and_then(|square| position.piece_at(square)) != Some(pawn)
+                {
+                    return Err(invalid());
+                }
+                Some(square)
+            }
+        });
+
+        let mut parse_counter = |default| match fields.next() {
+            Some(counter) => counter
+                .parse()
+                .map_err(|_| FenError::InvalidCounter(counter.to_string())),
+            None => Ok(default),
+        };
+        position.set_halfmove_clock(parse_counter(0)?);
+        position.set_fullmove_number(parse_counter(1)?);
+
+        Ok(position)
+    }
+
+    /// Writes the position in Forsyth-Edwards Notation. Chess960 castling rights are written
+    /// as in X-FEN, which is the same as FEN unless a rook stands between the king and the
+    /// rook it can castle with
+    pub fn to_fen(&self) -> String {
+        self.write_fen(false)
+    }
+
+    /// Writes the position in Shredder-FEN, which names the file of every rook that can castle
+    pub fn to_shredder_fen(&self) -> String {
+        self.write_fen(true)
+    }
+
+    /// The castling field, like KQkq or HAha
+    fn castling_field(&self, shredder: bool) -> String {
+        let mut castling = String::new();
+        for &color in [PieceColor::White, PieceColor::Black].iter() {
+            let rook = Piece::new(color, PieceType::Rook);
+            let is_rook =
+                |file: u8| self.piece_at(Square::new(file, color.back_rank())) == Some(rook);
+            for &king_side in [true, false].iter() {
+                let rook_file = match self.castling_rights().rook_file(color, king_side) {
+                    Some(v) => v,
+                    None => continue,
+                };
+                // Any other rook further out would be the one K or Q stand for
+                let outermost = if king_side {
+                    !(rook_file + 1..8).any(is_rook)
+                } else {
+                    !(0..rook_file).any(is_rook)
+                };
+                let c = if shredder || !outermost {
+                    (b'a' + rook_file) as char
+                } else if king_side {
+                    'k'
+                } else {
+                    'q'
+                };
+                castling.push(match color {
+                    PieceColor::White => c.to_ascii_uppercase(),
+                    PieceColor::Black => c,
+                });
+            }
+        }
+        if castling.is_empty() {
+            "-".to_string()
+        } else {
+            castling
+        }
+    }
+
+    fn write_fen(&self, shredder: bool) -> String {
+        let mut fen = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                match self.piece_at(Square::new(file, rank)) {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push(piece.to_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(match self.side_to_move() {
+            PieceColor::White => " w ",
+            PieceColor::Black => " b ",
+        });
+        fen.push_str(&self.castling_field(shredder));
+        match self.en_passant() {
+            Some(square) => fen.push_str(&format!(" {}", square)),
+            None => fen.push_str(" -"),
+        }
+        fen.push_str(&format!(
+            " {} {}",
+            self.halfmove_clock(),
+            self.fullmove_number()
+        ));
+        fen
+    }
+}
@@ -0,0 +1,162 @@
+use std::{error::Error, fmt};
+
+use crate::{Move, PieceColor, Position};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WinReason {
+    Checkmate,
+    /// The loser ran out of time
+    Timeout,
+    Resignation,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DrawReason {
+    Stalemate,
+    InsufficientMaterial,
+    /// Claimed by a player, when the position has been reached three times
+    ThreefoldRepetition,
+    /// Claimed by a player, after fifty moves by each side without a capture or pawn move
+    FiftyMoveRule,
+    /// The position was reached five times
+    FivefoldRepetition,
+    /// Seventy-five moves by each side were played without a capture or pawn move
+    SeventyFiveMoveRule,
+    /// The players agreed to a draw
+    Agreement,
+    /// A side ran out of time, but the other one couldn't have checkmated anyway
+    TimeoutVsInsufficientMaterial,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Outcome {
+    Win(PieceColor, WinReason),
+    Draw(DrawReason),
+}
+
+impl Outcome {
+    /// The outcome of the side to move running out of time in the position: a loss,
+    /// unless the other side couldn't checkmate by any series of legal moves
+    pub fn timeout(position: &Position, color: PieceColor) -> Self {
+        if position.has_mating_material(color.other()) {
+            Outcome::Win(color.other(), WinReason::Timeout)
+        } else {
+            Outcome::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct IllegalMoveError(pub Move);
+
+impl fmt::Display for IllegalMoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} is not a legal move", self.0)
+    }
+}
+
+impl Error for IllegalMoveError {}
+
+/// A game in progress: where it started and every move played since
+#[derive(Clone, Debug)]
+pub struct Game {
+    moves: Vec<Move>,
+    // The starting position followed by the position after each move
+    positions: Vec<Position>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Position::default())
+    }
+}
+
+impl Game {
+    pub fn new(start: Position) -> Self {
+        Game {
+            moves: Vec::new(),
+            positions: vec![start],
+        }
+    }
+
+    pub fn start_position(&self) -> &Position {
+        &self.positions[0]
+    }
+
+    /// The current position
+    pub fn position(&self) -> &Position {
+        self.positions.last().unwrap()
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn play(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
+        if !self.position().is_legal(mv) {
+            return Err(IllegalMoveError(mv));
+        }
+        let mut position = self.position().clone();
+        position.make_move(mv);
+        self.moves.push(mv);
+        self.positions.push(position);
+        Ok(())
+    }
+
+    /// Takes back moves until only the first `len` remain
+    pub fn truncate(&mut self, len: usize) {
+        self.moves.truncate(len);
+        self.positions.truncate(len + 1);
+    }
+
+    /// How many times the current position has been reached
+    pub fn repetitions(&self) -> usize {
+        let position = self.position();
+        let key = position.zobrist_key();
+        // Positions from before the last irreversible move can't repeat
+        self.positions
+            .iter()
+            .rev()
+            .take(position.halfmove_clock() as usize + 1)
+            .filter(|other| other.zobrist_key() == key)
+            .count()
+    }
+
+    /// Returns how the game ended, or None if it's still going on. Threefold repetition and
+    /// the fifty-move rule only end the game when claimed, see [`Game::claimable_draw`]
+    pub fn outcome(&self) -> Option<Outcome> {
+        let position = self.position();
+        if position.legal_moves().is_empty() {
+            // Without legal moves it's either checkmate or stalemate
+            return Some(if position.is_check() {
+                Outcome::Win(position.side_to_move().other(), WinReason::Checkmate)
+            } else {
+                Outcome::Draw(DrawReason::Stalemate)
+            });
+        }
+        if position.is_insufficient_material() {
+            Some(Outcome::Draw(DrawReason::InsufficientMaterial))
+        } else if self.repetitions() >= 5 {
+            Some(Outcome::Draw(DrawReason::FivefoldRepetition))
+        } else if position.halfmove_clock() >= 150 {
+            Some(Outcome::Draw(DrawReason::SeventyFiveMoveRule))
+        } else {
+            None
+        }
+    }
+
+    /// The draw the side to move can claim in the current position, if any
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.repetitions() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.position().halfmove_clock() >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+}
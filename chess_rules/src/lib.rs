@@ -0,0 +1,48 @@
+//! The rules of chess, with no dependency on Bevy or any other engine.
+//!
+//! A [`Position`] holds the board and everything needed to know which [`Move`]s
+//! are legal in it, and a [`Game`] keeps the history needed for the draw rules.
+//! A [`Search`] finds the best move to play in a game, remembering positions it has
+//! seen by their Zobrist key in a [`TranspositionTable`], and a [`UciEngine`] asks
+//! another engine for one. A [`Book`] has the moves of a Polyglot opening book, found by
+//! the position's [`polyglot_key`].
+
+mod attacks;
+mod bitboard;
+mod book;
+mod clock;
+mod eval;
+mod fen;
+mod game;
+mod moves;
+mod perft;
+mod pgn;
+mod piece;
+mod polyglot;
+mod position;
+mod san;
+mod search;
+mod square;
+mod tt;
+mod uci;
+mod zobrist;
+
+pub use bitboard::Bitboard;
+pub use book::{Book, BookEntry, BookError};
+pub use clock::{Clock, TimeBonus, TimeControl, TimeControlError, TimePeriod};
+pub use eval::{evaluate, piece_value};
+pub use fen::{FenError, START_FEN};
+pub use game::{DrawReason, Game, IllegalMoveError, Outcome, WinReason};
+pub use moves::Move;
+pub use perft::{divide, perft};
+pub use pgn::{GameResult, PgnError, PgnGame};
+pub use piece::{Piece, PieceColor, PieceType};
+pub use polyglot::polyglot_key;
+pub use position::{CastlingRights, Position, Undo};
+pub use san::SanError;
+pub use search::{Search, SearchInfo, SearchLimits, MATE_SCORE};
+pub use square::Square;
+pub use tt::{Bound, TranspositionTable, TtEntry, DEFAULT_TABLE_MB};
+pub use uci::{
+    parse_go, parse_position, position_command, GoLimits, UciEngine, UciError, UciInfo, UciScore,
+};
@@ -0,0 +1,215 @@
+use std::fmt;
+
+use crate::{
+    attacks::{
+        bishop_attacks, king_attacks, knight_attacks, pawn_attacks, queen_attacks, rook_attacks,
+    },
+    Bitboard, Piece, PieceType, Position, Square,
+};
+
+/// A move from one square to another. Castling is written as the king moving two squares
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub promotion: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Square, to: Square) -> Self {
+        Move {
+            from,
+            to,
+            promotion: None,
+        }
+    }
+
+    /// Parses the coordinate notation used by UCI, like e2e4 or e7e8q.
+    /// The move isn't checked to be legal in any position
+    pub fn from_uci(s: &str) -> Option<Self> {
+        if !s.is_ascii() || !(s.len() == 4 || s.len() == 5) {
+            return None;
+        }
+        let promotion = match s[4..].chars().next() {
+            Some(c) => match PieceType::from_letter(c.to_ascii_uppercase()) {
+                Some(PieceType::King) | Some(PieceType::Pawn) | None => return None,
+                piece_type => piece_type,
+            },
+            None => None,
+        };
+        Some(Move {
+            from: Square::from_algebraic(&s[0..2])?,
+            to: Square::from_algebraic(&s[2..4])?,
+            promotion,
+        })
+    }
+}
+
+/// Written in the coordinate notation used by UCI, like e2e4 or e7e8q
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(piece_type) = self.promotion {
+            write!(f, "{}", piece_type.letter().to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+impl Position {
+    /// Returns every move the side to move can make without leaving its king in check
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for from in self.color_bitboard(self.side_to_move()) {
+            self.add_pseudo_legal_moves(from, &mut moves);
+        }
+        self.retain_safe(&mut moves);
+        moves
+    }
+
+    /// Returns the legal moves of the piece on the square, which are none if it's
+    /// empty or the piece's side is not the one to move
+    pub fn legal_moves_from(&self, from: Square) -> Vec<Move> {
+        let mut moves = Vec::new();
+        match self.piece_at(from) {
+            Some(piece) if piece.color == self.side_to_move() => {
+                self.add_pseudo_legal_moves(from, &mut moves)
+            }
+            _ => {}
+        }
+        self.retain_safe(&mut moves);
+        moves
+    }
+
+    pub fn is_legal(&self, mv: Move) -> bool {
+        self.legal_moves_from(mv.from).contains(&mv)
+    }
+
+    /// Keeps the moves that don't leave the mover's king in check
+    fn retain_safe(&self, moves: &mut Vec<Move>) {
+        let color = self.side_to_move();
+        // Every move is tried on the same copy, and taken back before the next one
+        let mut after = self.clone();
+        moves.retain(|&mv| {
+            let undo = after.make_move(mv);
+            let safe = !after.is_check_on(color);
+            after.unmake_move(mv, undo);
+            safe
+        });
+    }
+
+    /// Adds the moves of the piece on from, without checking if they leave its king in check
+    fn add_pseudo_legal_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let piece = match self.piece_at(from) {
+            Some(v) => v,
+            _ => return,
+        };
+        let occupied = self.occupied();
+        let targets = match piece.piece_type {
+            PieceType::King => {
+                self.add_castling_moves(from, moves);
+                king_attacks(from)
+            }
+            PieceType::Knight => knight_attacks(from),
+            PieceType::Bishop => bishop_attacks(from, occupied),
+            PieceType::Rook => rook_attacks(from, occupied),
+            PieceType::Queen => queen_attacks(from, occupied),
+            PieceType::Pawn => return self.add_pawn_moves(from, moves),
+        };
+        for to in targets & !self.color_bitboard(piece.color) {
+            moves.push(Move::new(from, to));
+        }
+    }
+
+    fn add_pawn_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let color = self.side_to_move();
+        let forward = color.forward();
+        let mut add_pawn_move = |to: Square| {
+            // Reaching the last rank promotes, so each promotion is a different move
+            if to.rank() == color.other().back_rank() {
+                for &piece_type in PieceType::PROMOTIONS.iter() {
+                    moves.push(Move {
+                        from,
+                        to,
+                        promotion: Some(piece_type),
+                    });
+                }
+            } else {
+                moves.push(Move::new(from, to));
+            }
+        };
+
+        // Normal move
+        if let Some(to) = from.offset(0, forward) {
+            if self.piece_at(to).is_none() {
+                add_pawn_move(to);
+
+                // Double move
+                let start_rank = (color.back_rank() as i8 + forward) as u8;
+                if from.rank() == start_rank {
+                    if let Some(to) = to.offset(0, forward) {
+                        if self.piece_at(to).is_none() {
+                            add_pawn_move(to);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Take piece, including en passant
+        let mut takeable = self.color_bitboard(color.other());
+        if let Some(en_passant) = self.en_passant() {
+            takeable |= Bitboard::from_square(en_passant);
+        }
+        for to in pawn_attacks(color, from) & takeable {
+            add_pawn_move(to);
+        }
+    }
+
+    /// Adds the castling moves of the king on from. Wherever the king and rook start, as in
+    /// Chess960, they end on the same squares as in standard chess
+    fn add_castling_moves(&self, from: Square, moves: &mut Vec<Move>) {
+        let color = self.side_to_move();
+        if from.rank() != color.back_rank() || self.is_check_on(color) {
+            return;
+        }
+        for &king_side in [true, false].iter() {
+            let rook_file = match self.castling_rights().rook_file(color, king_side) {
+                Some(v) => v,
+                None => continue,
+            };
+            let rook = Square::new(rook_file, color.back_rank());
+            if self.piece_at(rook) != Some(Piece::new(color, PieceType::Rook))
+                || (rook_file > from.file()) != king_side
+            {
+                continue;
+            }
+            let (king_to, rook_to) = if king_side { (6, 5) } else { (2, 3) };
+            // Every square either piece goes over or lands on must be empty, but for themselves
+            let files = [from.file(), rook_file, king_to, rook_to];
+            let min_file = *files.iter().min().unwrap();
+            let max_file = *files.iter().max().unwrap();
+            if (min_file..=max_file).any(|file| {
+                let square = Square::new(file, color.back_rank());
+                square != from && square != rook && self.piece_at(square).is_some()
+            }) {
+                continue;
+            }
+            // The king can't castle through or into check
+            let (first, last) = (from.file().min(king_to), from.file().max(king_to));
+            if (first..=last)
+                .any(|file| self.is_attacked(Square::new(file, color.back_rank()), color.other()))
+            {
+                continue;
+            }
+            // Chess960 writes castling as the king taking its rook, since the king may not
+            // move at all, or only one square
+            let to = if self.is_chess960() {
+                rook
+            } else {
+                Square::new(king_to, color.back_rank())
+            };
+            moves.push(Move::new(from, to));
+        }
+    }
+}
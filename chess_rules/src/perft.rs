@@ -0,0 +1,44 @@
+use crate::{Move, Position};
+
+/// Counts the positions reached after every sequence of `depth` legal moves, which
+/// can be checked against known counts to find bugs in move generation
+pub fn perft(position: &Position, depth: u32) -> u64 {
+    count(&mut position.clone(), depth)
+}
+
+/// Like [`perft`], but split by the first move, so that a wrong count can be
+/// narrowed down one move at a time against another move generator
+pub fn divide(position: &Position, depth: u32) -> Vec<(Move, u64)> {
+    if depth == 0 {
+        return Vec::new();
+    }
+    let mut position = position.clone();
+    position
+        .legal_moves()
+        .into_iter()
+        .map(|mv| {
+            let undo = position.make_move(mv);
+            let nodes = count(&mut position, depth - 1);
+            position.unmake_move(mv, undo);
+            (mv, nodes)
+        })
+        .collect()
+}
+
+// Plays and takes back every move on the same position
+fn count(position: &mut Position, depth: u32) -> u64 {
+    match depth {
+        0 => 1,
+        // The moves themselves are the positions one move away
+        1 => position.legal_moves().len() as u64,
+        _ => {
+            let mut nodes = 0;
+            for mv in position.legal_moves() {
+                let undo = position.make_move(mv);
+                nodes += count(position, depth - 1);
+                position.unmake_move(mv, undo);
+            }
+            nodes
+        }
+    }
+}
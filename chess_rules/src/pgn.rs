@@ -0,0 +1,364 @@
+use std::{error::Error, fmt};
+
+use crate::{FenError, Game, Outcome, PieceColor, Position, SanError};
+
+#[derive(Debug, PartialEq)]
+pub enum PgnError {
+    InvalidTag {
+        game: usize,
+        tag: String,
+    },
+    InvalidFen {
+        game: usize,
+        error: FenError,
+    },
+    InvalidMove {
+        game: usize,
+        move_number: u32,
+        color: PieceColor,
+        error: SanError,
+    },
+    /// A variation was closed without being opened, or never closed
+    UnbalancedVariation {
+        game: usize,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::InvalidTag { game, tag } => write!(f, "game {}: invalid tag {}", game, tag),
+            PgnError::InvalidFen { game, error } => write!(f, "game {}: {}", game, error),
+            PgnError::InvalidMove {
+                game,
+                move_number,
+                color,
+                error,
+            } => {
+                let dots = match color {
+                    PieceColor::White => ".",
+                    PieceColor::Black => "...",
+                };
+                write!(f, "game {}, move {}{}: {}", game, move_number, dots, error)
+            }
+            PgnError::UnbalancedVariation { game } => {
+                write!(
+                    f,
+                    "game {}: unbalanced parentheses around a variation",
+                    game
+                )
+            }
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+/// The result as written in the Result tag and at the end of the movetext
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    /// The game is still going on, or was abandoned
+    Unknown,
+}
+
+impl GameResult {
+    pub fn from_outcome(outcome: Option<&Outcome>) -> Self {
+        match outcome {
+            Some(Outcome::Win(PieceColor::White, _)) => GameResult::WhiteWins,
+            Some(Outcome::Win(PieceColor::Black, _)) => GameResult::BlackWins,
+            Some(Outcome::Draw(_)) => GameResult::Draw,
+            None => GameResult::Unknown,
+        }
+    }
+
+    fn from_str(result: &str) -> Option<Self> {
+        match result {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        }
+    }
+}
+
+/// A game along with the tags describing it, as stored in a PGN file
+#[derive(Clone, Debug)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    pub result: GameResult,
+}
+
+impl PgnGame {
+    /// Creates the game with the Seven Tag Roster, leaving unknown tags as "?", or
+    /// "????.??.??" for the date. Games that don't start from the standard position also
+    /// get SetUp and FEN tags, and Chess960 games a Variant tag
+    pub fn new(game: Game, result: GameResult) -> Self {
+        let mut tags: Vec<_> = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+        ]
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+        tags.push(("Result".to_string(), result.as_str().to_string()));
+        if *game.start_position() != Position::default() {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), game.start_position().to_fen()));
+        }
+        if game.start_position().is_chess960() {
+            tags.push(("Variant".to_string(), "Chess960".to_string()));
+        }
+        PgnGame { tags, game, result }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces the value of the tag, adding it at the end if it's missing
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+}
+
+/// A game being read from a PGN file. The game itself is only set up once the
+/// first move is found, so the SetUp and FEN tags are known by then
+struct PgnReader {
+    number: usize,
+    tags: Vec<(String, String)>,
+    game: Option<Game>,
+}
+
+impl PgnReader {
+    fn new(number: usize) -> Self {
+        PgnReader {
+            number,
+            tags: Vec::new(),
+            game: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.game.is_none()
+    }
+
+    fn game(&mut self) -> Result<&mut Game, PgnError> {
+        if self.game.is_none() {
+            let mut position = match self.tags.iter().find(|(name, _)| name == "FEN") {
+                Some((_, fen)) => {
+                    Position::from_fen(fen).map_err(|error| PgnError::InvalidFen {
+                        game: self.number,
+                        error,
+                    })?
+                }
+                None => Position::default(),
+            };
+            // Even with the standard setup, castling goes by the Chess960 rules
+            let variant = self.tags.iter().find(|(name, _)| name == "Variant");
+            if let Some((_, variant)) = variant {
+                if variant.eq_ignore_ascii_case("chess960")
+                    || variant.eq_ignore_ascii_case("fischerandom")
+                {
+                    position.set_chess960(true);
+                }
+            }
+            self.game = Some(Game::new(position));
+        }
+        Ok(self.game.as_mut().unwrap())
+    }
+
+    fn play(&mut self, san: &str) -> Result<(), PgnError> {
+        let number = self.number;
+        let game = self.game()?;
+        let position = game.position();
+        let mv = position
+            .parse_san(san)
+            .map_err(|error| PgnError::InvalidMove {
+                game: number,
+                move_number: position.fullmove_number(),
+                color: position.side_to_move(),
+                error,
+            })?;
+        game.play(mv).unwrap();
+        Ok(())
+    }
+
+    fn finish(mut self, result: Option<GameResult>) -> Result<PgnGame, PgnError> {
+        let result = result
+            .or_else(|| {
+                self.tags
+                    .iter()
+                    .find(|(name, _)| name == "Result")
+                    .and_then(|(_, result)| GameResult::from_str(result))
+            })
+            .unwrap_or(GameResult::Unknown);
+        let game = self.game()?.clone();
+        Ok(PgnGame {
+            tags: self.tags,
+            game,
+            result,
+        })
+    }
+}
+
+/// Parses a tag pair like [Event "F/S Return Match"], returning None if it's malformed
+fn parse_tag(tag: &str) -> Option<(String, String)> {
+    let inner = tag.strip_prefix('[')?.strip_suffix(']')?.trim();
+    let name_end = inner.find(char::is_whitespace)?;
+    let name = &inner[..name_end];
+    let quoted = inner[name_end..].trim();
+    let quoted = quoted.strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut value = String::new();
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(chars.next()?),
+            _ => value.push(c),
+        }
+    }
+    Some((name.to_string(), value))
+}
+
+impl PgnGame {
+    /// Parses every game in a PGN file. Comments, variations and annotation glyphs are skipped
+    pub fn parse_all(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+        let mut games = Vec::new();
+        let mut reader = PgnReader::new(1);
+        let mut variation_depth = 0;
+        let mut chars = pgn.char_indices().peekable();
+
+        while let Some((start, c)) = chars.next() {
+            match c {
+                // Comments, escaped lines and annotation glyphs
+                '{' => {
+                    chars.by_ref().find(|&(_, c)| c == '}');
+                }
+                ';' => {
+                    chars.by_ref().find(|&(_, c)| c == '\n');
+                }
+                '%' if start == 0 || pgn[..start].ends_with('\n') => {
+                    chars.by_ref().find(|&(_, c)| c == '\n');
+                }
+                '$' => while chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {},
+                // Variations are alternatives to the main line, so they are skipped too
+                '(' => variation_depth += 1,
+                ')' if variation_depth == 0 => {
+                    return Err(PgnError::UnbalancedVariation {
+                        game: reader.number,
+                    })
+                }
+                ')' => variation_depth -= 1,
+                _ if variation_depth > 0 || c.is_whitespace() => {}
+                '[' => {
+                    let end = chars
+                        .by_ref()
+                        .find(|&(_, c)| c == ']')
+                        .map_or(pgn.len(), |(end, _)| end + 1);
+                    let tag = &pgn[start..end];
+                    let tag = parse_tag(tag).ok_or_else(|| PgnError::InvalidTag {
+                        game: reader.number,
+                        tag: tag.to_string(),
+                    })?;
+                    reader.tags.push(tag);
+                }
+                _ => {
+                    let mut end = start + c.len_utf8();
+                    while let Some(&(i, c)) = chars.peek() {
+                        if c.is_whitespace() || "{}()[];$".contains(c) {
+                            break;
+                        }
+                        end = i + c.len_utf8();
+                        chars.next();
+                    }
+                    let token = &pgn[start..end];
+
+                    if let Some(result) = GameResult::from_str(token) {
+                        let number = reader.number;
+                        games.push(reader.finish(Some(result))?);
+                        reader = PgnReader::new(number + 1);
+                        continue;
+                    }
+                    // Move numbers can be stuck to the move, like 1.e4 or 1...e5
+                    let san = token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                    if !san.is_empty() {
+                        reader.play(san)?;
+                    }
+                }
+            }
+        }
+        if variation_depth > 0 {
+            return Err(PgnError::UnbalancedVariation {
+                game: reader.number,
+            });
+        }
+        if !reader.is_empty() {
+            games.push(reader.finish(None)?);
+        }
+        Ok(games)
+    }
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, value) in &self.tags {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+
+        let mut tokens = Vec::new();
+        let positions = self.game.positions();
+        for (i, &mv) in self.game.moves().iter().enumerate() {
+            let position = &positions[i];
+            match position.side_to_move() {
+                PieceColor::White => tokens.push(format!("{}.", position.fullmove_number())),
+                PieceColor::Black if i == 0 => {
+                    tokens.push(format!("{}...", position.fullmove_number()))
+                }
+                PieceColor::Black => {}
+            }
+            tokens.push(position.to_san(mv));
+        }
+        tokens.push(self.result.as_str().to_string());
+
+        // Keep lines under 80 characters, as the export format asks
+        let mut line_length = 0;
+        for token in tokens {
+            if line_length > 0 && line_length + 1 + token.len() > 79 {
+                writeln!(f)?;
+                line_length = 0;
+            } else if line_length > 0 {
+                write!(f, " ")?;
+                line_length += 1;
+            }
+            write!(f, "{}", token)?;
+            line_length += token.len();
+        }
+        writeln!(f)
+    }
+}
@@ -0,0 +1,125 @@
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PieceColor {
+    White,
+    Black,
+}
+
+impl PieceColor {
+    pub fn other(&self) -> Self {
+        match self {
+            PieceColor::Black => PieceColor::White,
+            PieceColor::White => PieceColor::Black,
+        }
+    }
+
+    /// The direction pawns of this color advance in, in ranks
+    pub fn forward(&self) -> i8 {
+        match self {
+            PieceColor::White => 1,
+            PieceColor::Black => -1,
+        }
+    }
+
+    /// The rank pieces of this color start on, from 0 to 7
+    pub fn back_rank(&self) -> u8 {
+        match self {
+            PieceColor::White => 0,
+            PieceColor::Black => 7,
+        }
+    }
+
+    /// 0 for white and 1 for black, to index tables by color
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            PieceColor::White => 0,
+            PieceColor::Black => 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PieceType {
+    King,
+    Queen,
+    Bishop,
+    Knight,
+    Rook,
+    Pawn,
+}
+
+impl PieceType {
+    /// The piece types a pawn can promote to, strongest first
+    pub const PROMOTIONS: [PieceType; 4] = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+
+    /// From 0 to 5, to index tables by piece type
+    pub(crate) fn index(&self) -> usize {
+        match self {
+            PieceType::King => 0,
+            PieceType::Queen => 1,
+            PieceType::Bishop => 2,
+            PieceType::Knight => 3,
+            PieceType::Rook => 4,
+            PieceType::Pawn => 5,
+        }
+    }
+
+    /// The uppercase letter used for the piece in FEN and algebraic notation
+    pub fn letter(&self) -> char {
+        match self {
+            PieceType::King => 'K',
+            PieceType::Queen => 'Q',
+            PieceType::Bishop => 'B',
+            PieceType::Knight => 'N',
+            PieceType::Rook => 'R',
+            PieceType::Pawn => 'P',
+        }
+    }
+
+    /// The piece type for an uppercase letter, like in FEN and algebraic notation
+    pub fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'K' => Some(PieceType::King),
+            'Q' => Some(PieceType::Queen),
+            'B' => Some(PieceType::Bishop),
+            'N' => Some(PieceType::Knight),
+            'R' => Some(PieceType::Rook),
+            'P' => Some(PieceType::Pawn),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Piece {
+    pub color: PieceColor,
+    pub piece_type: PieceType,
+}
+
+impl Piece {
+    pub fn new(color: PieceColor, piece_type: PieceType) -> Self {
+        Piece { color, piece_type }
+    }
+
+    /// The FEN character for the piece: uppercase for white and lowercase for black
+    pub fn to_char(&self) -> char {
+        match self.color {
+            PieceColor::White => self.piece_type.letter(),
+            PieceColor::Black => self.piece_type.letter().to_ascii_lowercase(),
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Self> {
+        let color = if c.is_ascii_uppercase() {
+            PieceColor::White
+        } else {
+            PieceColor::Black
+        };
+        PieceType::from_letter(c.to_ascii_uppercase())
+            .map(|piece_type| Piece::new(color, piece_type))
+    }
+}
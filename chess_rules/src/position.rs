@@ -0,0 +1,605 @@
+use crate::{
+    attacks::{bishop_attacks, king_attacks, knight_attacks, pawn_attacks, rook_attacks},
+    zobrist::{castling_key, en_passant_key, piece_key, side_key},
+    Bitboard, Move, Piece, PieceColor, PieceType, Square,
+};
+
+const LAYOUT: [PieceType; 8] = [
+    PieceType::Rook,
+    PieceType::Knight,
+    PieceType::Bishop,
+    PieceType::Queen,
+    PieceType::King,
+    PieceType::Bishop,
+    PieceType::Knight,
+    PieceType::Rook,
+];
+
+/// Which sides each player can still castle to, as the file of the rook it would castle with.
+/// In standard chess these are always the a and h files, in Chess960 they can be any
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CastlingRights {
+    pub white_king_side: Option<u8>,
+    pub white_queen_side: Option<u8>,
+    pub black_king_side: Option<u8>,
+    pub black_queen_side: Option<u8>,
+}
+
+impl CastlingRights {
+    pub fn none() -> Self {
+        CastlingRights {
+            white_king_side: None,
+            white_queen_side: None,
+            black_king_side: None,
+            black_queen_side: None,
+        }
+    }
+
+    pub fn has(&self, color: PieceColor, king_side: bool) -> bool {
+        self.rook_file(color, king_side).is_some()
+    }
+
+    /// The file of the rook the side can castle with
+    pub fn rook_file(&self, color: PieceColor, king_side: bool) -> Option<u8> {
+        match (color, king_side) {
+            (PieceColor::White, true) => self.white_king_side,
+            (PieceColor::White, false) => self.white_queen_side,
+            (PieceColor::Black, true) => self.black_king_side,
+            (PieceColor::Black, false) => self.black_queen_side,
+        }
+    }
+
+    pub fn set(&mut self, color: PieceColor, king_side: bool, rook_file: Option<u8>) {
+        match (color, king_side) {
+            (PieceColor::White, true) => self.white_king_side = rook_file,
+            (PieceColor::White, false) => self.white_queen_side = rook_file,
+            (PieceColor::Black, true) => self.black_king_side = rook_file,
+            (PieceColor::Black, false) => self.black_queen_side = rook_file,
+        }
+    }
+}
+
+impl Default for CastlingRights {
+    fn default() -> Self {
+        CastlingRights {
+            white_king_side: Some(7),
+            white_queen_side: Some(0),
+            black_king_side: Some(7),
+            black_queen_side: Some(0),
+        }
+    }
+}
+
+/// Everything needed to know which moves are legal: the board, the side to move,
+/// castling rights and the en passant square, plus the move counters
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Position {
+    // The same pieces twice: by square to look them up, and as bitboards by color
+    // and by type to generate moves and find attacks
+    board: [Option<Piece>; 64],
+    colors: [Bitboard; 2],
+    piece_types: [Bitboard; 6],
+    side_to_move: PieceColor,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    chess960: bool,
+    // The Zobrist key of the pieces, side to move and castling rights, updated as they change
+    key: u64,
+}
+
+/// What [`Position::make_move`] changed that can't be worked out from the move itself,
+/// for [`Position::unmake_move`] to put back
+#[derive(Clone, Copy, Debug)]
+pub struct Undo {
+    // The piece that moved, before any promotion
+    piece: Option<Piece>,
+    landing: Square,
+    captured: Option<(Square, Piece)>,
+    castling_rook: Option<(Square, Square)>,
+    castling_rights: CastlingRights,
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    key: u64,
+}
+
+/// The standard starting position
+impl Default for Position {
+    fn default() -> Self {
+        Position::with_layout(&LAYOUT)
+    }
+}
+
+/// Where the knights go among the five squares left once the bishops and queen are
+/// placed, for each remainder of the Chess960 numbering
+const CHESS960_KNIGHTS: [(usize, usize); 10] = [
+    (0, 1),
+    (0, 2),
+    (0, 3),
+    (0, 4),
+    (1, 2),
+    (1, 3),
+    (1, 4),
+    (2, 3),
+    (2, 4),
+    (3, 4),
+];
+
+impl Position {
+    /// The Chess960 starting position with the given number, from 0 to 959, as numbered
+    /// by Reinhard Scharnagl. Number 518 is the standard starting position
+    pub fn chess960(index: u16) -> Option<Self> {
+        if index >= 960 {
+            return None;
+        }
+        let mut layout = [None; 8];
+        let mut index = index as usize;
+        // Bishops on light squares, then dark squares
+        layout[index % 4 * 2 + 1] = Some(PieceType::Bishop);
+        index /= 4;
+        layout[index % 4 * 2] = Some(PieceType::Bishop);
+        index /= 4;
+        let mut place_in_empty = |n: usize, piece_type: PieceType| {
+            let file = (0..8)
+                .filter(|&file| layout[file].is_none())
+                .nth(n)
+                .unwrap();
+            layout[file] = Some(piece_type);
+        };
+        place_in_empty(index % 6, PieceType::Queen);
+        index /= 6;
+        // Placing the second knight first keeps the first one's place among the empty squares
+        let (first_knight, second_knight) = CHESS960_KNIGHTS[index];
+        place_in_empty(second_knight, PieceType::Knight);
+        place_in_empty(first_knight, PieceType::Knight);
+        // The king always stands between the rooks
+        for &piece_type in [PieceType::Rook, PieceType::King, PieceType::Rook].iter() {
+            place_in_empty(0, piece_type);
+        }
+
+        let layout: Vec<_> = layout
+            .iter()
+            .map(|piece_type| piece_type.unwrap())
+            .collect();
+        let mut position = Position::with_layout(&layout);
+        position.chess960 = true;
+        Some(position)
+    }
+
+    /// A starting position with the pieces of the first rank in the given order,
+    /// and castling rights with the outermost rooks
+    fn with_layout(layout: &[PieceType]) -> Self {
+        let mut position = Position::empty();
+        for (file, &piece_type) in (0..).zip(layout.iter()) {
+            for &color in [PieceColor::White, PieceColor::Black].iter() {
+                let back_rank = color.back_rank();
+                let pawn_rank = (back_rank as i8 + color.forward()) as u8;
+                position.set_piece_at(
+                    Square::new(file, back_rank),
+                    Some(Piece::new(color, piece_type)),
+                );
+                position.set_piece_at(
+                    Square::new(file, pawn_rank),
+                    Some(Piece::new(color, PieceType::Pawn)),
+                );
+            }
+        }
+        let rook_files: Vec<_> = (0..)
+            .zip(layout.iter())
+            .filter(|(_, &piece_type)| piece_type == PieceType::Rook)
+            .map(|(file, _)| file)
+            .collect();
+        let mut castling_rights = CastlingRights::none();
+        for &color in [PieceColor::White, PieceColor::Black].iter() {
+            castling_rights.set(color, true, rook_files.last().copied());
+            castling_rights.set(color, false, rook_files.first().copied());
+        }
+        position.set_castling_rights(castling_rights);
+        position
+    }
+
+    /// An empty board with white to move and no castling rights
+    pub fn empty() -> Self {
+        Position {
+            board: [None; 64],
+            colors: [Bitboard::EMPTY; 2],
+            piece_types: [Bitboard::EMPTY; 6],
+            side_to_move: PieceColor::White,
+            castling_rights: CastlingRights::none(),
+            en_passant: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            chess960: false,
+            key: 0,
+        }
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.board[square.index()]
+    }
+
+    pub fn set_piece_at(&mut self, square: Square, piece: Option<Piece>) {
+        let bit = Bitboard::from_square(square);
+        if let Some(old) = self.board[square.index()] {
+            self.key ^= piece_key(old, square);
+            self.colors[old.color.index()] ^= bit;
+            self.piece_types[old.piece_type.index()] ^= bit;
+        }
+        if let Some(new) = piece {
+            self.key ^= piece_key(new, square);
+            self.colors[new.color.index()] |= bit;
+            self.piece_types[new.piece_type.index()] |= bit;
+        }
+        self.board[square.index()] = piece;
+    }
+
+    /// Iterates over every piece on the board along with its square, from a1 to h8
+    pub fn pieces(&self) -> impl Iterator<Item = (Square, Piece)> + '_ {
+        self.occupied()
+            .filter_map(move |square| self.piece_at(square).map(|piece| (square, piece)))
+    }
+
+    /// The squares of every piece on the board
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    /// The squares of the pieces of the color
+    pub fn color_bitboard(&self, color: PieceColor) -> Bitboard {
+        self.colors[color.index()]
+    }
+
+    /// The squares of the pieces of the color and type
+    pub fn bitboard(&self, color: PieceColor, piece_type: PieceType) -> Bitboard {
+        self.colors[color.index()] & self.piece_types[piece_type.index()]
+    }
+
+    pub fn side_to_move(&self) -> PieceColor {
+        self.side_to_move
+    }
+
+    pub fn set_side_to_move(&mut self, color: PieceColor) {
+        self.key ^= side_key(self.side_to_move) ^ side_key(color);
+        self.side_to_move = color;
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    pub fn set_castling_rights(&mut self, castling_rights: CastlingRights) {
+        self.key ^= castling_key(self.castling_rights) ^ castling_key(castling_rights);
+        self.castling_rights = castling_rights;
+    }
+
+    /// The square a pawn skipped over with a double move on the last turn
+    pub fn en_passant(&self) -> Option<Square> {
+        self.en_passant
+    }
+
+    pub fn set_en_passant(&mut self, en_passant: Option<Square>) {
+        self.en_passant = en_passant;
+    }
+
+    /// Half-moves since the last capture or pawn move, for the fifty-move rule
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn set_halfmove_clock(&mut self, halfmove_clock: u32) {
+        self.halfmove_clock = halfmove_clock;
+    }
+
+    /// Starts at 1 and goes up after every black move
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn set_fullmove_number(&mut self, fullmove_number: u32) {
+        self.fullmove_number = fullmove_number;
+    }
+
+    /// Whether castling moves are written as the king taking its own rook, as in Chess960,
+    /// rather than as the king moving two squares
+    pub fn is_chess960(&self) -> bool {
+        self.chess960
+    }
+
+    pub fn set_chess960(&mut self, chess960: bool) {
+        self.chess960 = chess960;
+    }
+
+    pub fn king_square(&self, color: PieceColor) -> Option<Square> {
+        self.bitboard(color, PieceType::King).first()
+    }
+
+    pub fn is_check(&self) -> bool {
+        self.is_check_on(self.side_to_move)
+    }
+
+    pub fn is_check_on(&self, color: PieceColor) -> bool {
+        match self.king_square(color) {
+            Some(square) => self.is_attacked(square, color.other()),
+            None => false,
+        }
+    }
+
+    pub fn is_checkmate(&self) -> bool {
+        self.is_check() && self.legal_moves().is_empty()
+    }
+
+    pub fn is_stalemate(&self) -> bool {
+        !self.is_check() && self.legal_moves().is_empty()
+    }
+
+    /// Returns true if neither side can possibly checkmate: bare kings, a single minor
+    /// piece, or only bishops that all stand on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let others: Vec<_> = self
+            .pieces()
+            .filter(|(_, piece)| piece.piece_type != PieceType::King)
+            .collect();
+        match others.as_slice() {
+            [] => true,
+            [(_, piece)] => {
+                piece.piece_type == PieceType::Bishop || piece.piece_type == PieceType::Knight
+            }
+            [(first, _), ..] => others.iter().all(|(square, piece)| {
+                piece.piece_type == PieceType::Bishop && square.is_light() == first.is_light()
+            }),
+        }
+    }
+
+    /// Returns true if the side could still checkmate with what both sides have, even if only
+    /// with the other side's help, which is what decides a loss on time. A lone knight needs
+    /// a piece of the other side other than a queen to hem the king in, and bishops that all
+    /// stand on squares of one color need a pawn, a knight or a bishop on the other color
+    pub fn has_mating_material(&self, color: PieceColor) -> bool {
+        let own = |piece_type| self.bitboard(color, piece_type);
+        let theirs = |piece_type| self.bitboard(color.other(), piece_type);
+        if !(own(PieceType::Pawn) | own(PieceType::Rook) | own(PieceType::Queen)).is_empty() {
+            return true;
+        }
+        let knights = own(PieceType::Knight).count();
+        let bishops = own(PieceType::Bishop);
+        match (knights, bishops.count()) {
+            (0, 0) => false,
+            (1, 0) => !(theirs(PieceType::Pawn)
+                | theirs(PieceType::Knight)
+                | theirs(PieceType::Bishop)
+                | theirs(PieceType::Rook))
+            .is_empty(),
+            (0, count) => {
+                let light = bishops.filter(Square::is_light).count() as u32;
+                let dark = count - light;
+                // Bishops of both colors, or another bishop on the color they're missing
+                light > 0 && dark > 0
+                    || !(theirs(PieceType::Pawn) | theirs(PieceType::Knight)).is_empty()
+                    || theirs(PieceType::Bishop).any(|square| square.is_light() != (light > 0))
+            }
+            _ => true,
+        }
+    }
+
+    /// Returns true if any piece of the attacker color attacks the square
+    pub fn is_attacked(&self, square: Square, attacker_color: PieceColor) -> bool {
+        let occupied = self.occupied();
+        let attackers = |piece_type| self.bitboard(attacker_color, piece_type);
+        let queens = attackers(PieceType::Queen);
+        // Pawns only attack diagonally, so look back from the square like a pawn of the other color
+        let attacked_by = (pawn_attacks(attacker_color.other(), square)
+            & attackers(PieceType::Pawn))
+            | (knight_attacks(square) & attackers(PieceType::Knight))
+            | (king_attacks(square) & attackers(PieceType::King))
+            | (bishop_attacks(square, occupied) & (attackers(PieceType::Bishop) | queens))
+            | (rook_attacks(square, occupied) & (attackers(PieceType::Rook) | queens));
+        !attacked_by.is_empty()
+    }
+
+    /// If the move castles, returns the square of the rook taking part and the square it ends on
+    pub fn castling_rook(&self, mv: Move) -> Option<(Square, Square)> {
+        let piece = self.piece_at(mv.from)?;
+        if piece.piece_type != PieceType::King {
+            return None;
+        }
+        let rook_from = if self.piece_at(mv.to) == Some(Piece::new(piece.color, PieceType::Rook)) {
+            mv.to
+        } else {
+            let file_delta = mv.to.file() as i8 - mv.from.file() as i8;
+            if self.chess960 || file_delta.abs() != 2 {
+                return None;
+            }
+            Square::new(if file_delta > 0 { 7 } else { 0 }, mv.from.rank())
+        };
+        let rook_file = if rook_from.file() > mv.from.file() {
+            5
+        } else {
+            3
+        };
+        Some((rook_from, Square::new(rook_file, mv.from.rank())))
+    }
+
+    /// The square the moving piece ends on. That's the target square of the move, except
+    /// when castling in Chess960, where the king ends on the c or g file
+    pub fn landing_square(&self, mv: Move) -> Square {
+        match self.castling_rook(mv) {
+            Some((rook_from, _)) if rook_from.file() > mv.from.file() => {
+                Square::new(6, mv.from.rank())
+            }
+            Some(_) => Square::new(2, mv.from.rank()),
+            None => mv.to,
+        }
+    }
+
+    /// Returns the square of the piece the move takes, which is not the target square
+    /// for en passant captures
+    pub fn captured_square(&self, mv: Move) -> Option<Square> {
+        let piece = self.piece_at(mv.from)?;
+        match self.piece_at(mv.to) {
+            // Castling in Chess960 is written as the king taking its own rook
+            Some(other) if other.color == piece.color => None,
+            Some(_) => Some(mv.to),
+            None if piece.piece_type == PieceType::Pawn && Some(mv.to) == self.en_passant => {
+                Some(Square::new(mv.to.file(), mv.from.rank()))
+            }
+            None => None,
+        }
+    }
+
+    pub fn is_capture(&self, mv: Move) -> bool {
+        self.captured_square(mv).is_some()
+    }
+
+    /// Returns true if the move is a capture or a pawn move, which can never be undone
+    pub fn is_irreversible(&self, mv: Move) -> bool {
+        self.is_capture(mv)
+            || self.piece_at(mv.from).map(|piece| piece.piece_type) == Some(PieceType::Pawn)
+    }
+
+    /// Returns true if a pawn move to the last rank needs a promotion piece
+    pub fn is_promotion(&self, from: Square, to: Square) -> bool {
+        match self.piece_at(from) {
+            Some(piece) => {
+                piece.piece_type == PieceType::Pawn && to.rank() == piece.color.other().back_rank()
+            }
+            None => false,
+        }
+    }
+
+    /// Applies the move without checking if it's legal. The returned [`Undo`] takes it
+    /// back with [`Position::unmake_move`]
+    pub fn make_move(&mut self, mv: Move) -> Undo {
+        let mut undo = Undo {
+            piece: self.piece_at(mv.from),
+            landing: mv.to,
+            captured: None,
+            castling_rook: None,
+            castling_rights: self.castling_rights,
+            en_passant: self.en_passant,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            key: self.key,
+        };
+        let piece = match undo.piece {
+            Some(v) => v,
+            _ => return undo,
+        };
+        let color = piece.color;
+
+        if self.is_irreversible(mv) {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if color == PieceColor::Black {
+            self.fullmove_number += 1;
+        }
+
+        undo.landing = self.landing_square(mv);
+        undo.castling_rook = self.castling_rook(mv);
+        if let Some(captured) = self.captured_square(mv) {
+            undo.captured = self.piece_at(captured).map(|piece| (captured, piece));
+            self.set_piece_at(captured, None);
+        }
+        // In Chess960 the king and rook can land on each other's squares, so both
+        // leave the board before either is put back
+        self.set_piece_at(mv.from, None);
+        if let Some((rook_from, rook_to)) = undo.castling_rook {
+            self.set_piece_at(rook_from, None);
+            self.set_piece_at(rook_to, Some(Piece::new(color, PieceType::Rook)));
+        }
+        self.set_piece_at(
+            undo.landing,
+            Some(Piece::new(color, mv.promotion.unwrap_or(piece.piece_type))),
+        );
+
+        // Only the pawn that just double moved can be taken en passant
+        let rank_delta = mv.to.rank() as i8 - mv.from.rank() as i8;
+        self.en_passant = if piece.piece_type == PieceType::Pawn && rank_delta.abs() == 2 {
+            mv.from.offset(0, rank_delta / 2)
+        } else {
+            None
+        };
+
+        // Moving the king or a rook, or losing a rook, gives up castling on that side
+        if piece.piece_type == PieceType::King {
+            self.castling_rights.set(color, true, None);
+            self.castling_rights.set(color, false, None);
+        }
+        for &square in [mv.from, mv.to].iter() {
+            for &color in [PieceColor::White, PieceColor::Black].iter() {
+                for &king_side in [true, false].iter() {
+                    let rook_file = self.castling_rights.rook_file(color, king_side);
+                    if rook_file.map(|file| Square::new(file, color.back_rank())) == Some(square) {
+                        self.castling_rights.set(color, king_side, None);
+                    }
+                }
+            }
+        }
+
+        self.key ^= castling_key(undo.castling_rights) ^ castling_key(self.castling_rights);
+        self.key ^= side_key(color) ^ side_key(color.other());
+        self.side_to_move = color.other();
+        undo
+    }
+
+    /// Takes back the move, which must be the last one made on this position
+    pub fn unmake_move(&mut self, mv: Move, undo: Undo) {
+        self.castling_rights = undo.castling_rights;
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        let piece = match undo.piece {
+            Some(v) => v,
+            _ => return,
+        };
+        self.side_to_move = piece.color;
+
+        self.set_piece_at(undo.landing, None);
+        if let Some((rook_from, rook_to)) = undo.castling_rook {
+            self.set_piece_at(rook_to, None);
+            self.set_piece_at(rook_from, Some(Piece::new(piece.color, PieceType::Rook)));
+        }
+        self.set_piece_at(mv.from, Some(piece));
+        if let Some((square, captured)) = undo.captured {
+            self.set_piece_at(square, Some(captured));
+        }
+        self.key = undo.key;
+    }
+
+    /// The en passant square, but only if a legal en passant capture exists
+    pub fn legal_en_passant(&self) -> Option<Square> {
+        let en_passant = self.en_passant?;
+        let color = self.side_to_move;
+        // Only the pawns beside the one that just double moved can take it
+        let can_capture = (pawn_attacks(color.other(), en_passant)
+            & self.bitboard(color, PieceType::Pawn))
+        .any(|from| self.is_legal(Move::new(from, en_passant)));
+        if can_capture {
+            Some(en_passant)
+        } else {
+            None
+        }
+    }
+
+    /// A 64-bit Zobrist key of the pieces, side to move, castling rights and the file of
+    /// a possible en passant capture. Positions that count as the same one for the
+    /// repetition rule have the same key, and different positions almost never do
+    pub fn zobrist_key(&self) -> u64 {
+        match self.legal_en_passant() {
+            Some(square) => self.key ^ en_passant_key(square.file()),
+            None => self.key,
+        }
+    }
+
+    /// Returns true if both positions count as the same one for the repetition rule:
+    /// same pieces, side to move, castling rights and possible en passant captures
+    pub fn is_repetition_of(&self, other: &Position) -> bool {
+        self.board == other.board
+            && self.side_to_move == other.side_to_move
+            && self.castling_rights == other.castling_rights
+            && self.legal_en_passant() == other.legal_en_passant()
+    }
+}
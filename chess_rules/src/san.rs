@@ -0,0 +1,188 @@
+use std::{error::Error, fmt};
+
+use crate::{Move, PieceType, Position, Square};
+
+#[derive(Debug, PartialEq)]
+pub enum SanError {
+    /// Not written in Standard Algebraic Notation
+    Invalid(String),
+    /// No legal move matches
+    Illegal(String),
+    /// More than one legal move matches
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::Invalid(san) => write!(f, "{} is not a valid move", san),
+            SanError::Illegal(san) => write!(f, "{} is not a legal move", san),
+            SanError::Ambiguous(san) => write!(f, "{} is ambiguous", san),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+impl Position {
+    /// Writes a legal move in Standard Algebraic Notation, like Nbd7, exd6, O-O or e8=Q+
+    pub fn to_san(&self, mv: Move) -> String {
+        let piece = match self.piece_at(mv.from) {
+            Some(v) => v,
+            _ => return mv.to_string(),
+        };
+
+        let mut san = match self.castling_rook(mv) {
+            Some(_) if mv.to.file() > mv.from.file() => "O-O".to_string(),
+            Some(_) => "O-O-O".to_string(),
+            None => {
+                let mut san = String::new();
+                if piece.piece_type == PieceType::Pawn {
+                    if self.is_capture(mv) {
+                        san.push((b'a' + mv.from.file()) as char);
+                    }
+                } else {
+                    san.push(piece.piece_type.letter());
+                    san.push_str(&self.disambiguation(mv));
+                }
+                if self.is_capture(mv) {
+                    san.push('x');
+                }
+                san.push_str(&mv.to.to_string());
+                if let Some(piece_type) = mv.promotion {
+                    san.push('=');
+                    san.push(piece_type.letter());
+                }
+                san
+            }
+        };
+
+        let mut after = self.clone();
+        after.make_move(mv);
+        if after.is_checkmate() {
+            san.push('#');
+        } else if after.is_check() {
+            san.push('+');
+        }
+        san
+    }
+
+    /// Finds the legal move written in Standard Algebraic Notation. Check and annotation
+    /// suffixes are ignored, and castling can also be written with zeros
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let invalid = || SanError::Invalid(san.to_string());
+        let trimmed = san.trim_end_matches(&['+', '#', '!', '?'][..]);
+        if !trimmed.is_ascii() {
+            return Err(invalid());
+        }
+
+        let candidates: Vec<_> = match trimmed {
+            "O-O" | "0-0" | "O-O-O" | "0-0-0" => {
+                let king_side = trimmed.len() == 3;
+                self.legal_moves()
+                    .into_iter()
+                    .filter(|&mv| {
+                        self.castling_rook(mv).is_some()
+                            && (mv.to.file() > mv.from.file()) == king_side
+                    })
+                    .collect()
+            }
+            _ => {
+                // Promotions are usually written as e8=Q, but e8Q is also seen
+                let (body, promotion) = match trimmed.find('=') {
+                    Some(i) => {
+                        let letter = trimmed[i + 1..].chars().next().ok_or_else(invalid)?;
+                        (
+                            &trimmed[..i],
+                            Some(PieceType::from_letter(letter).ok_or_else(invalid)?),
+                        )
+                    }
+                    None => match trimmed.as_bytes() {
+                        [.., b'1'..=b'8', letter @ (b'Q' | b'R' | b'B' | b'N')] => (
+                            &trimmed[..trimmed.len() - 1],
+                            PieceType::from_letter(*letter as char),
+                        ),
+                        _ => (trimmed, None),
+                    },
+                };
+                let (piece_type, rest) = match body.chars().next().and_then(PieceType::from_letter)
+                {
+                    Some(piece_type) => (piece_type, &body[1..]),
+                    None => (PieceType::Pawn, body),
+                };
+                if rest.len() < 2 {
+                    return Err(invalid());
+                }
+                let to = Square::from_algebraic(&rest[rest.len() - 2..]).ok_or_else(invalid)?;
+
+                // Whatever is left before the target square tells apart the origin
+                let mut from_file = None;
+                let mut from_rank = None;
+                for c in rest[..rest.len() - 2].chars() {
+                    match c {
+                        'a'..='h' => from_file = Some(c as u8 - b'a'),
+                        '1'..='8' => from_rank = Some(c as u8 - b'1'),
+                        'x' | ':' => {}
+                        _ => return Err(invalid()),
+                    }
+                }
+
+                self.legal_moves()
+                    .into_iter()
+                    .filter(|&mv| {
+                        mv.to == to
+                            && mv.promotion == promotion
+                            && self.piece_at(mv.from).map(|piece| piece.piece_type)
+                                == Some(piece_type)
+                            && (from_file.is_none() || from_file == Some(mv.from.file()))
+                            && (from_rank.is_none() || from_rank == Some(mv.from.rank()))
+                    })
+                    .collect()
+            }
+        };
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(SanError::Illegal(san.to_string())),
+            _ => Err(SanError::Ambiguous(san.to_string())),
+        }
+    }
+
+    /// Finds the legal move typed by a player, either in Standard Algebraic Notation
+    /// like Nf3, or in the coordinate notation used by UCI like g1f3
+    pub fn parse_move(&self, s: &str) -> Result<Move, SanError> {
+        let s = s.trim();
+        match Move::from_uci(s) {
+            Some(mv) if self.is_legal(mv) => Ok(mv),
+            _ => self.parse_san(s),
+        }
+    }
+
+    /// The file and/or rank needed to tell the move apart from moves of other
+    /// pieces of the same type to the same square
+    fn disambiguation(&self, mv: Move) -> String {
+        let piece = self.piece_at(mv.from);
+        let others: Vec<_> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to && other.from != mv.from && self.piece_at(other.from) == piece
+            })
+            .collect();
+        if others.is_empty() {
+            String::new()
+        } else if others
+            .iter()
+            .all(|other| other.from.file() != mv.from.file())
+        {
+            ((b'a' + mv.from.file()) as char).to_string()
+        } else if others
+            .iter()
+            .all(|other| other.from.rank() != mv.from.rank())
+        {
+            (mv.from.rank() + 1).to_string()
+        } else {
+            mv.from.to_string()
+        }
+    }
+}
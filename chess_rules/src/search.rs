@@ -0,0 +1,375 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    eval::{evaluate, piece_value},
+    tt::{Bound, TranspositionTable, TtEntry, DEFAULT_TABLE_MB},
+    Game, Move, PieceType, Position,
+};
+
+/// The score of checkmating right away. Mates further away score a bit less,
+/// one point per half-move, so that the quickest one is preferred
+pub const MATE_SCORE: i32 = 100_000;
+const INFINITY: i32 = 1_000_000;
+const MAX_PLY: usize = 128;
+// How often the clock and the stop flag are looked at, in nodes (a power of two)
+const CHECK_INTERVAL: u64 = 1024;
+
+/// When to stop searching. With no limits at all, the search goes on until stopped
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub time: Option<Duration>,
+}
+
+/// What the search found after completing an iteration
+#[derive(Clone, Debug)]
+pub struct SearchInfo {
+    pub depth: u32,
+    /// In centipawns, from the point of view of the side to move
+    pub score: i32,
+    pub nodes: u64,
+    pub time: Duration,
+    /// The best line found, starting with the move to play
+    pub pv: Vec<Move>,
+}
+
+impl SearchInfo {
+    /// The number of moves until checkmate if the score is a mate score,
+    /// negative when the side to move is the one getting mated
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE_SCORE - self.score.abs();
+        if plies > MAX_PLY as i32 {
+            return None;
+        }
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+/// Iterative-deepening alpha-beta search, with a quiescence search of captures at the leaves.
+/// What it finds is kept in a transposition table, which later runs make use of too
+pub struct Search {
+    limits: SearchLimits,
+    stop: Arc<AtomicBool>,
+    started: Instant,
+    nodes: u64,
+    aborted: bool,
+    // Whether the search may stop before the current iteration is complete
+    can_abort: bool,
+    // The Zobrist keys of the positions of the game followed by those of the line being searched
+    history: Vec<u64>,
+    root_best: Option<Move>,
+    killers: Vec<[Option<Move>; 2]>,
+    table: TranspositionTable,
+}
+
+impl Search {
+    pub fn new(limits: SearchLimits) -> Self {
+        Search {
+            limits,
+            stop: Arc::new(AtomicBool::new(false)),
+            started: Instant::now(),
+            nodes: 0,
+            aborted: false,
+            can_abort: false,
+            history: Vec::new(),
+            root_best: None,
+            killers: vec![[None; 2]; MAX_PLY],
+            table: TranspositionTable::new(DEFAULT_TABLE_MB),
+        }
+    }
+
+    /// Sets the limits of the next run. The stop handle of the last run
+    /// is replaced with a new one, which isn't set
+    pub fn set_limits(&mut self, limits: SearchLimits) {
+        self.limits = limits;
+        self.stop = Arc::new(AtomicBool::new(false));
+    }
+
+    /// Replaces the transposition table with an empty one of about the given number of megabytes
+    pub fn resize_table(&mut self, megabytes: usize) {
+        self.table = TranspositionTable::new(megabytes);
+    }
+
+    /// Forgets what earlier runs found, like when a new game starts
+    pub fn clear_table(&mut self) {
+        self.table.clear();
+    }
+
+    /// A flag that stops the search when set, from any thread.
+    /// The best move of the last completed iteration is returned
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Searches the current position of the game, calling `on_info` after every
+    /// completed iteration. Returns the best move, or `None` if there are no legal moves
+    pub fn run(&mut self, game: &Game, mut on_info: impl FnMut(&SearchInfo)) -> Option<Move> {
+        self.started = Instant::now();
+        self.nodes = 0;
+        self.aborted = false;
+        self.can_abort = false;
+        self.history = game.positions().iter().map(Position::zobrist_key).collect();
+        self.root_best = None;
+
+        let position = game.position().clone();
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY as u32 / 2);
+        for depth in 1..=max_depth {
+            let mut pv = Vec::new();
+            let score = self.negamax(&position, depth, 0, -INFINITY, INFINITY, &mut pv);
+            if self.aborted {
+                break;
+            }
+            self.can_abort = true;
+            self.root_best = pv.first().copied();
+            let info = SearchInfo {
+                depth,
+                score,
+                nodes: self.nodes,
+                time: self.started.elapsed(),
+                pv,
+            };
+            on_info(&info);
+
+            // Once a mate is found, searching deeper won't find anything better
+            if info.mate_in().is_some() || self.root_best.is_none() {
+                break;
+            }
+            // The next iteration would take longer than what's left anyway
+            if let Some(time) = self.limits.time {
+                if self.started.elapsed() * 2 > time {
+                    break;
+                }
+            }
+        }
+        self.root_best
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.can_abort && !self.aborted && self.nodes & (CHECK_INTERVAL - 1) == 0 {
+            let out_of_time = match self.limits.time {
+                Some(time) => self.started.elapsed() >= time,
+                None => false,
+            };
+            self.aborted = out_of_time || self.stop.load(Ordering::Relaxed);
+        }
+        self.aborted
+    }
+
+    /// Draws by repetition, the fifty-move rule or insufficient material.
+    /// A single repetition is enough, since the side that could avoid it would
+    fn is_draw(&self, position: &Position, key: u64) -> bool {
+        position.halfmove_clock() >= 100
+            || position.is_insufficient_material()
+            || self
+                .history
+                .iter()
+                .rev()
+                .skip(1)
+                .take(position.halfmove_clock() as usize)
+                .any(|&other| other == key)
+    }
+
+    fn negamax(
+        &mut self,
+        position: &Position,
+        depth: u32,
+        ply: usize,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<Move>,
+    ) -> i32 {
+        pv.clear();
+        if self.should_stop() {
+            return 0;
+        }
+        let key = position.zobrist_key();
+        if ply > 0 && self.is_draw(position, key) {
+            return 0;
+        }
+        if ply >= MAX_PLY - 1 {
+            return evaluate(position);
+        }
+
+        // Don't stop searching in the middle of a check
+        let in_check = position.is_check();
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth == 0 {
+            return self.quiescence(position, ply, alpha, beta);
+        }
+
+        // A deep enough search of the same position may already have the answer
+        let mut table_move = None;
+        if let Some(entry) = self.table.get(key) {
+            table_move = entry.best_move;
+            let score = score_from_table(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if ply > 0 && entry.depth >= depth && usable {
+                return score;
+            }
+        }
+
+        self.nodes += 1;
+        let mut moves = position.legal_moves();
+        if moves.is_empty() {
+            return if in_check {
+                -MATE_SCORE + ply as i32
+            } else {
+                0
+            };
+        }
+        let best = if ply == 0 { self.root_best } else { None }.or(table_move);
+        self.order_moves(position, &mut moves, best, ply);
+
+        let original_alpha = alpha;
+        let mut child_pv = Vec::new();
+        for mv in moves {
+            let mut child = position.clone();
+            child.make_move(mv);
+            self.history.push(child.zobrist_key());
+            let score = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha, &mut child_pv);
+            self.history.pop();
+            if self.aborted {
+                return 0;
+            }
+
+            if score >= beta {
+                if !position.is_capture(mv) {
+                    let killers = &mut self.killers[ply];
+                    if killers[0] != Some(mv) {
+                        killers[1] = killers[0];
+                        killers[0] = Some(mv);
+                    }
+                }
+                self.store(key, depth, Bound::Lower, beta, Some(mv), ply);
+                return beta;
+            }
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mv);
+                pv.extend_from_slice(&child_pv);
+            }
+        }
+        if alpha > original_alpha {
+            self.store(key, depth, Bound::Exact, alpha, pv.first().copied(), ply);
+        } else {
+            self.store(key, depth, Bound::Upper, alpha, table_move, ply);
+        }
+        alpha
+    }
+
+    fn store(
+        &mut self,
+        key: u64,
+        depth: u32,
+        bound: Bound,
+        score: i32,
+        best_move: Option<Move>,
+        ply: usize,
+    ) {
+        self.table.insert(TtEntry {
+            key,
+            depth,
+            bound,
+            score: score_to_table(score, ply),
+            best_move,
+        });
+    }
+
+    /// Only looks at captures and queen promotions, so that the position is quiet
+    /// when it gets evaluated
+    fn quiescence(&mut self, position: &Position, ply: usize, mut alpha: i32, beta: i32) -> i32 {
+        self.nodes += 1;
+        if self.should_stop() {
+            return 0;
+        }
+
+        // The side to move can usually do at least as well as doing nothing
+        let stand_pat = evaluate(position);
+        if stand_pat >= beta || ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<_> = position
+            .legal_moves()
+            .into_iter()
+            .filter(|&mv| position.is_capture(mv) || mv.promotion == Some(PieceType::Queen))
+            .collect();
+        self.order_moves(position, &mut moves, None, ply);
+        for mv in moves {
+            let mut child = position.clone();
+            child.make_move(mv);
+            let score = -self.quiescence(&child, ply + 1, -beta, -alpha);
+            if self.aborted {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+        alpha
+    }
+
+    /// Puts the moves most likely to be good first, which lets alpha-beta cut more:
+    /// the best move so far, then captures of the most valuable pieces by the least
+    /// valuable ones, then moves that caused a cutoff at the same depth before
+    fn order_moves(&self, position: &Position, moves: &mut [Move], best: Option<Move>, ply: usize) {
+        let killers = self.killers[ply];
+        moves.sort_by_cached_key(|&mv| {
+            let mut score = 0;
+            if Some(mv) == best {
+                score += 1_000_000;
+            }
+            if let Some(square) = position.captured_square(mv) {
+                let victim = position
+                    .piece_at(square)
+                    .map_or(0, |p| piece_value(p.piece_type));
+                let attacker = position
+                    .piece_at(mv.from)
+                    .map_or(0, |p| piece_value(p.piece_type));
+                score += 10_000 + 10 * victim - attacker / 10;
+            } else if killers.contains(&Some(mv)) {
+                score += 5_000;
+            }
+            if let Some(promotion) = mv.promotion {
+                score += piece_value(promotion);
+            }
+            -score
+        });
+    }
+}
+
+/// Mate scores count the half-moves from the root, but the same position can be reached at any
+/// ply, so they're stored counting from the position itself instead
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
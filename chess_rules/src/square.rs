@@ -0,0 +1,67 @@
+use std::fmt;
+
+/// One of the 64 squares of the board, a1 being 0 and h8 being 63
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
+pub struct Square(u8);
+
+impl Square {
+    /// Creates a square from its file (0 is the a file) and rank (0 is the first rank)
+    pub fn new(file: u8, rank: u8) -> Self {
+        debug_assert!(file < 8 && rank < 8);
+        Square(rank * 8 + file)
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        debug_assert!(index < 64);
+        Square(index as u8)
+    }
+
+    /// Iterates over all the squares, from a1 to h8
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+
+    pub fn file(&self) -> u8 {
+        self.0 % 8
+    }
+
+    pub fn rank(&self) -> u8 {
+        self.0 / 8
+    }
+
+    /// Returns the square at the given distance, or None if it's off the board
+    pub fn offset(&self, file_delta: i8, rank_delta: i8) -> Option<Square> {
+        let file = self.file() as i8 + file_delta;
+        let rank = self.rank() as i8 + rank_delta;
+        if (0..8).contains(&file) && (0..8).contains(&rank) {
+            Some(Square::new(file as u8, rank as u8))
+        } else {
+            None
+        }
+    }
+
+    /// Parses a square written like e4
+    pub fn from_algebraic(name: &str) -> Option<Self> {
+        match name.as_bytes() {
+            &[file @ b'a'..=b'h', rank @ b'1'..=b'8'] => {
+                Some(Square::new(file - b'a', rank - b'1'))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true for the light squares, like h1
+    pub fn is_light(&self) -> bool {
+        (self.file() + self.rank()) % 2 == 1
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
@@ -0,0 +1,75 @@
+use std::mem;
+
+use crate::Move;
+
+/// The size of the table a [`Search`](crate::Search) starts with, in megabytes
+pub const DEFAULT_TABLE_MB: usize = 16;
+
+/// What a stored score says about the real one, since alpha-beta stops looking
+/// at a position as soon as it knows it won't be played
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bound {
+    Exact,
+    /// The real score is at least this, after a move good enough to cut the search
+    Lower,
+    /// The real score is at most this, since no move reached what was hoped for
+    Upper,
+}
+
+/// What a search found about a position
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TtEntry {
+    /// The Zobrist key of the position
+    pub key: u64,
+    /// How many half-moves deep the position was searched
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<Move>,
+}
+
+/// A fixed-size table of what searches found, by the Zobrist key of the position, so
+/// that positions reached again through other moves aren't searched again.
+/// Each key has a single slot, and newer entries push out older ones
+pub struct TranspositionTable {
+    entries: Vec<Option<TtEntry>>,
+}
+
+impl TranspositionTable {
+    /// A table using about the given number of megabytes, and at least one entry
+    pub fn new(megabytes: usize) -> Self {
+        let len = megabytes * 1024 * 1024 / mem::size_of::<Option<TtEntry>>();
+        TranspositionTable {
+            entries: vec![None; len.max(1)],
+        }
+    }
+
+    /// How many entries the table can hold
+    pub fn capacity(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn slot(&self, key: u64) -> usize {
+        (key % self.entries.len() as u64) as usize
+    }
+
+    /// The entry for the position with the key, if it's still in the table
+    pub fn get(&self, key: u64) -> Option<TtEntry> {
+        self.entries[self.slot(key)].filter(|entry| entry.key == key)
+    }
+
+    /// Stores the entry, unless there's a deeper one for the same position already
+    pub fn insert(&mut self, entry: TtEntry) {
+        let slot = self.slot(entry.key);
+        match self.entries[slot] {
+            Some(old) if old.key == entry.key && old.depth > entry.depth => {}
+            _ => self.entries[slot] = Some(entry),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter_mut() {
+            *entry = None;
+        }
+    }
+}
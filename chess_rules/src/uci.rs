@@ -0,0 +1,432 @@
+use std::{
+    error::Error,
+    ffi::OsStr,
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{Game, Move, PieceColor, Position, SearchInfo, SearchLimits};
+
+/// How long the engine gets to answer `uci` and `isready`, and to send its move once told to stop
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// How much longer than its limits the engine may think before it's told to stop
+const GO_MARGIN: Duration = Duration::from_secs(1);
+/// How long a search limited by depth alone may take
+const DEPTH_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug)]
+pub enum UciError {
+    Io(io::Error),
+    /// The engine didn't answer the command in time
+    Timeout(&'static str),
+    /// The engine closed its output, usually because it exited
+    Disconnected,
+    /// The engine sent a `bestmove` that isn't a move
+    InvalidBestMove(String),
+}
+
+impl fmt::Display for UciError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UciError::Io(err) => write!(f, "could not talk to the engine: {}", err),
+            UciError::Timeout(command) => write!(f, "the engine didn't answer {}", command),
+            UciError::Disconnected => write!(f, "the engine exited"),
+            UciError::InvalidBestMove(s) => write!(f, "the engine sent an invalid move: {}", s),
+        }
+    }
+}
+
+impl Error for UciError {}
+
+impl From<io::Error> for UciError {
+    fn from(err: io::Error) -> Self {
+        UciError::Io(err)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UciScore {
+    /// In centipawns, from the point of view of the side to move
+    Centipawns(i32),
+    /// Moves until checkmate, negative when the side to move gets mated
+    Mate(i32),
+}
+
+/// What an engine reports in its `info` lines. Anything it leaves out is `None`
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct UciInfo {
+    pub depth: Option<u32>,
+    pub score: Option<UciScore>,
+    pub nodes: Option<u64>,
+    pub time: Option<Duration>,
+    pub pv: Vec<Move>,
+}
+
+impl UciInfo {
+    /// Parses an `info` line, ignoring the fields it doesn't know about
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("info") {
+            return None;
+        }
+        let mut info = UciInfo::default();
+        while let Some(word) = words.next() {
+            match word {
+                "depth" => info.depth = words.next().and_then(|s| s.parse().ok()),
+                "nodes" => info.nodes = words.next().and_then(|s| s.parse().ok()),
+                "time" => {
+                    info.time = words
+                        .next()
+                        .and_then(|s| s.parse().ok())
+                        .map(Duration::from_millis)
+                }
+                "score" => {
+                    let kind = words.next();
+                    let value = words.next().and_then(|s| s.parse().ok());
+                    info.score = match (kind, value) {
+                        (Some("cp"), Some(v)) => Some(UciScore::Centipawns(v)),
+                        (Some("mate"), Some(v)) => Some(UciScore::Mate(v)),
+                        _ => None,
+                    };
+                }
+                // The principal variation runs until the end of the line
+                "pv" => info.pv = words.by_ref().map_while(Move::from_uci).collect(),
+                // The rest of the line is free text
+                "string" => break,
+                _ => {}
+            }
+        }
+        Some(info)
+    }
+}
+
+impl From<&SearchInfo> for UciInfo {
+    fn from(info: &SearchInfo) -> Self {
+        UciInfo {
+            depth: Some(info.depth),
+            score: Some(match info.mate_in() {
+                Some(moves) => UciScore::Mate(moves),
+                None => UciScore::Centipawns(info.score),
+            }),
+            nodes: Some(info.nodes),
+            time: Some(info.time),
+            pv: info.pv.clone(),
+        }
+    }
+}
+
+/// Written as an `info` line
+impl fmt::Display for UciInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "info")?;
+        if let Some(depth) = self.depth {
+            write!(f, " depth {}", depth)?;
+        }
+        match self.score {
+            Some(UciScore::Centipawns(score)) => write!(f, " score cp {}", score)?,
+            Some(UciScore::Mate(moves)) => write!(f, " score mate {}", moves)?,
+            None => {}
+        }
+        if let Some(nodes) = self.nodes {
+            write!(f, " nodes {}", nodes)?;
+        }
+        if let Some(time) = self.time {
+            write!(f, " time {}", time.as_millis())?;
+        }
+        if !self.pv.is_empty() {
+            write!(f, " pv")?;
+            for mv in &self.pv {
+                write!(f, " {}", mv)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How long the engine may think, as sent with `go`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GoLimits {
+    MoveTime(Duration),
+    Clock {
+        white_time: Duration,
+        black_time: Duration,
+        white_increment: Duration,
+        black_increment: Duration,
+    },
+    Depth(u32),
+}
+
+impl fmt::Display for GoLimits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GoLimits::MoveTime(time) => write!(f, "go movetime {}", time.as_millis()),
+            GoLimits::Clock {
+                white_time,
+                black_time,
+                white_increment,
+                black_increment,
+            } => write!(
+                f,
+                "go wtime {} btime {} winc {} binc {}",
+                white_time.as_millis(),
+                black_time.as_millis(),
+                white_increment.as_millis(),
+                black_increment.as_millis()
+            ),
+            GoLimits::Depth(depth) => write!(f, "go depth {}", depth),
+        }
+    }
+}
+
+impl GoLimits {
+    /// How long the engine should take at most when the side to move is `turn`
+    fn time_limit(&self, turn: PieceColor) -> Duration {
+        match *self {
+            GoLimits::MoveTime(time) => time,
+            GoLimits::Clock {
+                white_time,
+                black_time,
+                ..
+            } => match turn {
+                PieceColor::White => white_time,
+                PieceColor::Black => black_time,
+            },
+            GoLimits::Depth(_) => DEPTH_TIMEOUT,
+        }
+    }
+}
+
+/// The `position` command that sets up the game on the engine's side. Chess960 games are
+/// sent with X-FEN, which engines expect once `UCI_Chess960` is on
+pub fn position_command(game: &Game) -> String {
+    let mut command = if game.start_position() == &Position::default() {
+        "position startpos".to_string()
+    } else {
+        format!("position fen {}", game.start_position().to_fen())
+    };
+    if !game.moves().is_empty() {
+        command.push_str(" moves");
+        for mv in game.moves() {
+            command.push(' ');
+            command.push_str(&mv.to_string());
+        }
+    }
+    command
+}
+
+/// Sets up the game from the arguments of `position startpos|fen <FEN> [moves <MOVE>...]`,
+/// as an engine receives them. In Chess960 castling moves are written as the king taking
+/// its rook, even from the standard setup
+pub fn parse_position(args: &[&str], chess960: bool) -> Result<Game, String> {
+    let moves_start = args
+        .iter()
+        .position(|&arg| arg == "moves")
+        .unwrap_or(args.len());
+    let mut position = match args.first() {
+        Some(&"startpos") => Position::default(),
+        Some(&"fen") => {
+            let fen = args[1..moves_start].join(" ");
+            Position::from_fen(&fen).map_err(|err| format!("invalid FEN: {}", err))?
+        }
+        _ => return Err("expected startpos or fen".to_string()),
+    };
+    if chess960 {
+        position.set_chess960(true);
+    }
+
+    let mut game = Game::new(position);
+    for arg in args.iter().skip(moves_start + 1) {
+        let mv = Move::from_uci(arg).ok_or_else(|| format!("invalid move: {}", arg))?;
+        game.play(mv).map_err(|err| err.to_string())?;
+    }
+    Ok(game)
+}
+
+/// Reads the value after a `go` parameter, like the 1000 in `movetime 1000`
+fn go_param(args: &[&str], name: &str) -> Option<u64> {
+    let i = args.iter().position(|&arg| arg == name)?;
+    args.get(i + 1)?.parse().ok()
+}
+
+/// Works out the search limits from the arguments of `go depth|movetime|wtime ...|infinite`.
+/// Also returns whether the search should go on until it's stopped
+pub fn parse_go(args: &[&str], side_to_move: PieceColor) -> (SearchLimits, bool) {
+    let (time, increment) = match side_to_move {
+        PieceColor::White => (go_param(args, "wtime"), go_param(args, "winc")),
+        PieceColor::Black => (go_param(args, "btime"), go_param(args, "binc")),
+    };
+    let mut limits = SearchLimits {
+        depth: go_param(args, "depth").map(|depth| depth as u32),
+        time: go_param(args, "movetime").map(Duration::from_millis),
+    };
+    if let Some(time) = time {
+        // Spread the time left over the moves until the next time control, or over the
+        // rest of the game without one, keeping a margin for the GUI
+        let moves_to_go = go_param(args, "movestogo").unwrap_or(30).max(1);
+        let increment = increment.unwrap_or(0);
+        let budget = (time / moves_to_go + increment * 3 / 4)
+            .min(time.saturating_sub(50))
+            .max(10);
+        limits.time = Some(Duration::from_millis(budget));
+    }
+    let infinite = args.contains(&"infinite") || (limits.depth.is_none() && limits.time.is_none());
+    (limits, infinite)
+}
+
+/// An engine running in another process, spoken to through the Universal Chess Interface
+pub struct UciEngine {
+    process: Child,
+    stdin: ChildStdin,
+    // Lines the engine writes, read on their own thread so that reading can time out
+    lines: Receiver<String>,
+    name: Option<String>,
+    // Whether the engine was told to play Chess960
+    chess960: bool,
+}
+
+impl UciEngine {
+    /// Starts the engine and waits until it's ready to play
+    pub fn spawn(program: impl AsRef<OsStr>) -> Result<Self, UciError> {
+        Self::spawn_command(Command::new(program))
+    }
+
+    /// Like [`UciEngine::spawn`], for engines that need arguments or a working directory
+    pub fn spawn_command(mut command: Command) -> Result<Self, UciError> {
+        let mut process = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = process.stdin.take().unwrap();
+        let stdout = process.stdout.take().unwrap();
+
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut engine = UciEngine {
+            process,
+            stdin,
+            lines,
+            name: None,
+            chess960: false,
+        };
+        engine.send("uci")?;
+        loop {
+            let line = engine.read_line(HANDSHAKE_TIMEOUT, "uci")?;
+            if line.trim() == "uciok" {
+                break;
+            }
+            if let Some(name) = line.trim().strip_prefix("id name ") {
+                engine.name = Some(name.to_string());
+            }
+        }
+        engine.wait_ready()?;
+        Ok(engine)
+    }
+
+    /// The name the engine gave itself
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn send(&mut self, command: &str) -> Result<(), UciError> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    fn read_line(&mut self, timeout: Duration, command: &'static str) -> Result<String, UciError> {
+        self.lines.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => UciError::Timeout(command),
+            RecvTimeoutError::Disconnected => UciError::Disconnected,
+        })
+    }
+
+    pub fn wait_ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        while self.read_line(HANDSHAKE_TIMEOUT, "isready")?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    /// Tells the engine that the next position is from another game
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.wait_ready()
+    }
+
+    /// Asks for a move in the current position of the game, calling `on_info` with
+    /// what the engine reports while thinking. Returns `None` if the engine has no move
+    /// to play. The move is only known to be well formed, not legal. An engine that goes
+    /// past its limits is told to stop, and fails with a timeout if it still doesn't answer
+    pub fn go(
+        &mut self,
+        game: &Game,
+        limits: GoLimits,
+        mut on_info: impl FnMut(&UciInfo),
+    ) -> Result<Option<Move>, UciError> {
+        // Castling moves are written differently in Chess960, so the engine has to know
+        let chess960 = game.start_position().is_chess960();
+        if chess960 != self.chess960 {
+            self.send(&format!("setoption name UCI_Chess960 value {}", chess960))?;
+            self.chess960 = chess960;
+        }
+        self.send(&position_command(game))?;
+        self.send(&limits.to_string())?;
+        let mut deadline =
+            Instant::now() + limits.time_limit(game.position().side_to_move()) + GO_MARGIN;
+        let mut stopped = false;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let line = match self.read_line(timeout, "go") {
+                Err(UciError::Timeout(_)) if !stopped => {
+                    self.send("stop")?;
+                    stopped = true;
+                    deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+                    continue;
+                }
+                line => line?,
+            };
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("info") => {
+                    if let Some(info) = UciInfo::parse(&line) {
+                        on_info(&info);
+                    }
+                }
+                Some("bestmove") => {
+                    return match words.next() {
+                        None | Some("(none)") | Some("0000") => Ok(None),
+                        Some(s) => Move::from_uci(s)
+                            .map(Some)
+                            .ok_or_else(|| UciError::InvalidBestMove(s.to_string())),
+                    };
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        // Engines are supposed to exit on quit, but don't leave one behind if it doesn't
+        let _ = self.send("quit");
+        for _ in 0..10 {
+            if let Ok(Some(_)) = self.process.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
@@ -0,0 +1,90 @@
+//! Random numbers for Zobrist keys: a position's key is all the numbers for what's in it
+//! XORed together, so that a move only has to XOR in and out what it changes.
+//! They're worked out at compile time, so keys are the same from run to run
+
+use crate::{CastlingRights, Piece, PieceColor, Square};
+
+/// A fixed seed, so that the numbers never change
+const SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+/// The SplitMix64 generator, which is good enough for keys and simple enough to run in a const fn
+const fn split_mix(state: u64) -> (u64, u64) {
+    let state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    (state, z ^ (z >> 31))
+}
+
+struct Keys {
+    // By color, then piece type, then square
+    pieces: [[[u64; 64]; 6]; 2],
+    // By color, then the file of the rook it can castle with
+    castling: [[u64; 8]; 2],
+    en_passant: [u64; 8],
+    black_to_move: u64,
+}
+
+const fn keys() -> Keys {
+    let mut keys = Keys {
+        pieces: [[[0; 64]; 6]; 2],
+        castling: [[0; 8]; 2],
+        en_passant: [0; 8],
+        black_to_move: 0,
+    };
+    let mut state = SEED;
+    let mut i = 0;
+    while i < 2 * 6 * 64 {
+        let (next, key) = split_mix(state);
+        state = next;
+        keys.pieces[i / 384][i / 64 % 6][i % 64] = key;
+        i += 1;
+    }
+    i = 0;
+    while i < 2 * 8 {
+        let (next, key) = split_mix(state);
+        state = next;
+        keys.castling[i / 8][i % 8] = key;
+        i += 1;
+    }
+    i = 0;
+    while i < 8 {
+        let (next, key) = split_mix(state);
+        state = next;
+        keys.en_passant[i] = key;
+        i += 1;
+    }
+    keys.black_to_move = split_mix(state).1;
+    keys
+}
+
+const KEYS: Keys = keys();
+
+pub(crate) fn piece_key(piece: Piece, square: Square) -> u64 {
+    KEYS.pieces[piece.color.index()][piece.piece_type.index()][square.index()]
+}
+
+/// The key of every castling right together. Rights are told apart by the rook's file,
+/// which keeps different Chess960 rights apart too
+pub(crate) fn castling_key(castling_rights: CastlingRights) -> u64 {
+    let mut key = 0;
+    for &color in [PieceColor::White, PieceColor::Black].iter() {
+        for &king_side in [true, false].iter() {
+            if let Some(file) = castling_rights.rook_file(color, king_side) {
+                key ^= KEYS.castling[color.index()][file as usize];
+            }
+        }
+    }
+    key
+}
+
+pub(crate) fn en_passant_key(file: u8) -> u64 {
+    KEYS.en_passant[file as usize]
+}
+
+pub(crate) fn side_key(color: PieceColor) -> u64 {
+    match color {
+        PieceColor::White => 0,
+        PieceColor::Black => KEYS.black_to_move,
+    }
+}
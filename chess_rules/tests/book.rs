@@ -0,0 +1,143 @@
+use chess_rules::{polyglot_key, Book, BookError, Game, Move, Position};
+
+// Packs an entry the way Polyglot books store it
+fn entry(key: u64, mv: &str, weight: u16) -> Vec<u8> {
+    let square = |name: &str| {
+        let bytes = name.as_bytes();
+        ((bytes[1] - b'1') as u16) * 8 + (bytes[0] - b'a') as u16
+    };
+    let promotion = match mv.as_bytes().get(4) {
+        Some(b'n') => 1,
+        Some(b'b') => 2,
+        Some(b'r') => 3,
+        Some(b'q') => 4,
+        _ => 0,
+    };
+    let raw_move = promotion << 12 | square(&mv[0..2]) << 6 | square(&mv[2..4]);
+    let mut bytes = key.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&raw_move.to_be_bytes());
+    bytes.extend_from_slice(&weight.to_be_bytes());
+    bytes.extend_from_slice(&0u32.to_be_bytes());
+    bytes
+}
+
+fn book(entries: &[(u64, &str, u16)]) -> Book {
+    let bytes: Vec<u8> = entries
+        .iter()
+        .flat_map(|&(key, mv, weight)| entry(key, mv, weight))
+        .collect();
+    Book::from_bytes(&bytes).unwrap()
+}
+
+#[test]
+fn keys_match_the_published_ones() {
+    // The test positions given with the Polyglot format, reached from the start
+    let lines: [(&[&str], u64); 9] = [
+        (&[], 0x463b_9618_1691_fc9c),
+        (&["e2e4"], 0x823c_9b50_fd11_4196),
+        (&["e2e4", "d7d5"], 0x0756_b944_61c5_0fb0),
+        (&["e2e4", "d7d5", "e4e5"], 0x662f_afb9_65db_29d4),
+        (&["e2e4", "d7d5", "e4e5", "f7f5"], 0x22a4_8b5a_8e47_ff78),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2"],
+            0x652a_607c_a3f2_42c1,
+        ),
+        (
+            &["e2e4", "d7d5", "e4e5", "f7f5", "e1e2", "e8f7"],
+            0x00fd_d303_c946_bdd9,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4"],
+            0x3c81_23ea_7b06_7637,
+        ),
+        (
+            &["a2a4", "b7b5", "h2h4", "b5b4", "c2c4", "b4c3", "a1a3"],
+            0x5c3f_9b82_9b27_9560,
+        ),
+    ];
+    for (moves, key) in lines.iter() {
+        let mut game = Game::default();
+        for mv in moves.iter() {
+            game.play(Move::from_uci(mv).unwrap()).unwrap();
+        }
+        assert_eq!(polyglot_key(game.position()), *key, "{:?}", moves);
+    }
+}
+
+#[test]
+fn positions_are_looked_up_by_their_key() {
+    let start = polyglot_key(&Position::default());
+    let book = book(&[(start, "e2e4", 3), (start, "d2d4", 1), (start, "e2e5", 5)]);
+    let position = Position::default();
+    assert_eq!(
+        book.moves(&position),
+        [
+            (Move::from_uci("e2e4").unwrap(), 3),
+            (Move::from_uci("d2d4").unwrap(), 1)
+        ]
+    );
+    assert_eq!(book.choose_move(&position, 0), Move::from_uci("e2e4"));
+    assert_eq!(book.choose_move(&position, 3), Move::from_uci("d2d4"));
+    // The illegal move still takes up its share of the weight
+    assert_eq!(book.choose_move(&position, 4), None);
+    let after = Position::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1");
+    assert!(book.moves(&after.unwrap()).is_empty());
+}
+
+#[test]
+fn entries_are_found_by_key() {
+    let book = book(&[
+        (1, "e2e4", 10),
+        (5, "d2d4", 30),
+        (5, "c2c4", 10),
+        (5, "g1f3", 0),
+        (9, "e7e5", 1),
+    ]);
+    assert_eq!(book.len(), 5);
+    assert_eq!(book.entries(5).len(), 3);
+    assert!(book.entries(4).is_empty());
+    assert!(book.entries(10).is_empty());
+
+    let position = Position::default();
+    let moves: Vec<_> = book
+        .entries(5)
+        .iter()
+        .map(|entry| entry.to_move(&position).unwrap().to_string())
+        .collect();
+    assert_eq!(moves, ["d2d4", "c2c4", "g1f3"]);
+}
+
+#[test]
+fn choices_follow_the_weights() {
+    let book = book(&[(5, "d2d4", 30), (5, "c2c4", 10), (5, "g1f3", 0)]);
+    let picks: Vec<_> = (0..40)
+        .map(|random| book.choose(5, random).unwrap().weight)
+        .collect();
+    assert_eq!(picks.iter().filter(|&&weight| weight == 30).count(), 30);
+    assert_eq!(picks.iter().filter(|&&weight| weight == 10).count(), 10);
+    assert_eq!(book.choose(6, 0), None);
+}
+
+#[test]
+fn castling_is_written_as_taking_the_rook() {
+    let book = book(&[(1, "e1h1", 1), (1, "e1a1", 1)]);
+    let position = Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let moves: Vec<_> = book
+        .entries(1)
+        .iter()
+        .map(|entry| entry.to_move(&position))
+        .collect();
+    assert_eq!(moves, [Move::from_uci("e1g1"), Move::from_uci("e1c1")]);
+}
+
+#[test]
+fn illegal_and_truncated_entries_are_rejected() {
+    let book = book(&[(1, "e2e5", 1), (1, "e7e8q", 1)]);
+    for entry in book.entries(1) {
+        assert_eq!(entry.to_move(&Position::default()), None);
+    }
+    assert!(matches!(
+        Book::from_bytes(&[0; 20]),
+        Err(BookError::Truncated)
+    ));
+}
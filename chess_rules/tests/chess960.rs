@@ -0,0 +1,146 @@
+use chess_rules::{Move, PieceColor, PieceType, Position, Square, START_FEN};
+
+fn sq(s: &str) -> Square {
+    Square::from_algebraic(s).unwrap()
+}
+
+fn mv(s: &str) -> Move {
+    Move::from_uci(s).unwrap()
+}
+
+/// The pieces of White's first rank, like RNBQKBNR
+fn first_rank(position: &Position) -> String {
+    (0..8)
+        .map(|file| {
+            position
+                .piece_at(Square::new(file, 0))
+                .map_or('.', |piece| piece.to_char())
+        })
+        .collect()
+}
+
+#[test]
+fn start_positions_by_number() {
+    let standard = Position::chess960(518).unwrap();
+    assert_eq!(standard.to_fen(), START_FEN);
+    assert!(standard.is_chess960());
+
+    let first = Position::chess960(0).unwrap();
+    assert_eq!(
+        first.to_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1"
+    );
+    assert_eq!(
+        first.to_shredder_fen(),
+        "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w HFhf - 0 1"
+    );
+    assert_eq!(first_rank(&Position::chess960(959).unwrap()), "RKRNNQBB");
+    assert!(Position::chess960(960).is_none());
+}
+
+#[test]
+fn every_start_position_is_different_and_valid() {
+    let mut seen = std::collections::HashSet::new();
+    for index in 0..960 {
+        let position = Position::chess960(index).unwrap();
+        let rank = first_rank(&position);
+        let files = |piece: char| -> Vec<usize> {
+            rank.char_indices()
+                .filter(|&(_, c)| c == piece)
+                .map(|(file, _)| file)
+                .collect()
+        };
+        let (bishops, rooks, king) = (files('B'), files('R'), files('K')[0]);
+        assert_ne!(bishops[0] % 2, bishops[1] % 2, "{}", rank);
+        assert!(rooks[0] < king && king < rooks[1], "{}", rank);
+        assert!(seen.insert(rank));
+    }
+}
+
+#[test]
+fn castling_lands_on_the_standard_squares() {
+    // The king on b1 castles with the rooks on a1 and g1
+    let position = Position::from_fen("7k/8/8/8/8/8/8/RK4R1 w KQ - 0 1").unwrap();
+    assert!(position.is_chess960());
+    assert_eq!(position.castling_rights().white_king_side, Some(6));
+    assert_eq!(position.castling_rights().white_queen_side, Some(0));
+
+    let king_side = mv("b1g1");
+    assert!(position.is_legal(king_side));
+    assert_eq!(position.to_san(king_side), "O-O");
+    assert_eq!(position.parse_san("O-O"), Ok(king_side));
+    let mut after = position.clone();
+    after.make_move(king_side);
+    assert_eq!(first_rank(&after), "R....RK.");
+    assert_eq!(after.castling_rights().white_queen_side, None);
+
+    let queen_side = mv("b1a1");
+    assert_eq!(position.parse_san("O-O-O"), Ok(queen_side));
+    let mut after = position.clone();
+    after.make_move(queen_side);
+    assert_eq!(first_rank(&after), "..KR..R.");
+}
+
+#[test]
+fn castling_without_moving_the_king() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/6KR w H - 0 1").unwrap();
+    let castle = mv("g1h1");
+    assert!(position.is_legal(castle));
+    assert!(!position.is_capture(castle));
+    assert_eq!(position.landing_square(castle), sq("g1"));
+    let mut after = position.clone();
+    after.make_move(castle);
+    assert_eq!(first_rank(&after), ".....RK.");
+}
+
+#[test]
+fn castling_needs_a_free_and_safe_path() {
+    // The rook on f8 attacks the square the king crosses to castle king side
+    let position = Position::from_fen("5r1k/8/8/8/8/8/8/RK5R w KQ - 0 1").unwrap();
+    assert!(!position.is_legal(mv("b1h1")));
+    assert!(position.is_legal(mv("b1a1")));
+    // A knight in the way of the rook landing on d1
+    let position = Position::from_fen("7k/8/8/8/8/8/8/RK1N3R w KQ - 0 1").unwrap();
+    assert!(!position.is_legal(mv("b1a1")));
+}
+
+#[test]
+fn x_fen_names_inner_rooks() {
+    // Only the rook on d1 can castle, with another one further out on h1
+    let fen = "4k3/8/8/8/8/8/8/1K1R3R w D - 0 1";
+    let position = Position::from_fen(fen).unwrap();
+    assert_eq!(position.castling_rights().white_king_side, Some(3));
+    assert_eq!(position.to_fen(), fen);
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/1K1R3R w K - 0 1")
+            .unwrap()
+            .castling_rights()
+            .white_king_side,
+        Some(7)
+    );
+}
+
+#[test]
+fn shredder_fen_of_the_standard_setup() {
+    let position =
+        Position::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w HAha - 0 1").unwrap();
+    assert!(position.is_chess960());
+    assert_eq!(
+        position.castling_rights(),
+        Position::default().castling_rights()
+    );
+    // Castling takes the rook, rather than going two squares
+    assert!(!Position::default().is_chess960());
+    let mut position = Position::from_fen("4k3/8/8/8/8/8/8/R3K2R w HA - 0 1").unwrap();
+    assert!(position.is_legal(mv("e1h1")));
+    assert!(!position.is_legal(mv("e1g1")));
+    position.make_move(mv("e1h1"));
+    assert_eq!(
+        position.piece_at(sq("g1")).map(|piece| piece.piece_type),
+        Some(PieceType::King)
+    );
+    assert_eq!(
+        position.piece_at(sq("f1")).map(|piece| piece.color),
+        Some(PieceColor::White)
+    );
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use chess_rules::{Clock, PieceColor, TimeBonus, TimeControl, TimePeriod};
+
+fn secs(seconds: u64) -> Duration {
+    Duration::from_secs(seconds)
+}
+
+#[test]
+fn time_controls_parse_and_print() {
+    assert_eq!(
+        "300".parse::<TimeControl>().unwrap(),
+        TimeControl::sudden_death(secs(300))
+    );
+    assert_eq!(
+        "180+2".parse::<TimeControl>().unwrap(),
+        TimeControl::fischer(secs(180), secs(2))
+    );
+    assert_eq!(
+        "300d5".parse::<TimeControl>().unwrap(),
+        TimeControl::simple_delay(secs(300), secs(5))
+    );
+    assert_eq!(
+        "300b5".parse::<TimeControl>().unwrap(),
+        TimeControl::bronstein(secs(300), secs(5))
+    );
+
+    let classical: TimeControl = "40/7200:3600+30".parse().unwrap();
+    assert_eq!(
+        classical.periods,
+        vec![
+            TimePeriod {
+                moves: Some(40),
+                time: secs(7200)
+            },
+            TimePeriod {
+                moves: None,
+                time: secs(3600)
+            },
+        ]
+    );
+    assert_eq!(classical.bonus, TimeBonus::Increment(secs(30)));
+    assert_eq!(classical.to_string(), "40/7200:3600+30");
+
+    for invalid in &["", "abc", "5+", "3600:40/7200", "0/60", "-5", "inf", "NaN"] {
+        assert!(invalid.parse::<TimeControl>().is_err(), "{}", invalid);
+    }
+    // Too long for a Duration
+    for invalid in &["1e20", "60+1e20", "40/1e20:60"] {
+        assert!(invalid.parse::<TimeControl>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn sudden_death_flags() {
+    let mut clock = Clock::new(TimeControl::sudden_death(secs(10)), PieceColor::White);
+    clock.tick(secs(4));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::White), secs(6));
+    assert_eq!(clock.turn(), PieceColor::Black);
+
+    clock.tick(secs(3));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(7));
+    clock.press();
+    clock.tick(secs(6));
+    assert_eq!(clock.flagged(), Some(PieceColor::White));
+    assert_eq!(clock.remaining(PieceColor::White), secs(0));
+}
+
+#[test]
+fn fischer_adds_the_increment() {
+    let mut clock = Clock::new(TimeControl::fischer(secs(60), secs(2)), PieceColor::White);
+    clock.tick(secs(5));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::White), secs(57));
+}
+
+#[test]
+fn bronstein_gives_back_at_most_the_delay() {
+    let mut clock = Clock::new(TimeControl::bronstein(secs(60), secs(3)), PieceColor::White);
+    clock.tick(secs(2));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::White), secs(60));
+    clock.tick(secs(5));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::Black), secs(58));
+}
+
+#[test]
+fn simple_delay_waits_before_running() {
+    let mut clock = Clock::new(
+        TimeControl::simple_delay(secs(60), secs(5)),
+        PieceColor::White,
+    );
+    clock.tick(secs(3));
+    assert_eq!(clock.remaining(PieceColor::White), secs(60));
+    assert_eq!(clock.delay_left(), secs(2));
+    clock.tick(secs(4));
+    assert_eq!(clock.remaining(PieceColor::White), secs(58));
+    clock.press();
+    assert_eq!(clock.remaining(PieceColor::White), secs(58));
+}
+
+#[test]
+fn periods_add_time_after_their_moves() {
+    let control: TimeControl = "2/100:50".parse().unwrap();
+    let mut clock = Clock::new(control, PieceColor::White);
+    for _ in 0..2 {
+        clock.tick(secs(10));
+        clock.press();
+        clock.tick(secs(1));
+        clock.press();
+    }
+    assert_eq!(clock.remaining(PieceColor::White), secs(130));
+    assert_eq!(clock.remaining(PieceColor::Black), secs(148));
+}
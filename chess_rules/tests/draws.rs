@@ -0,0 +1,132 @@
+use chess_rules::{DrawReason, Game, Move, Outcome, PieceColor, Position, WinReason};
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for mv in moves {
+        game.play(Move::from_uci(mv).unwrap()).unwrap();
+    }
+}
+
+// Both knights go out and back, bringing back the starting position
+const SHUFFLE: [&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+#[test]
+fn threefold_repetition_is_claimed() {
+    let mut game = Game::default();
+    play(&mut game, &SHUFFLE);
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, &SHUFFLE);
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+    assert_eq!(game.outcome(), None);
+}
+
+#[test]
+fn fivefold_repetition_ends_the_game() {
+    let mut game = Game::default();
+    for _ in 0..3 {
+        play(&mut game, &SHUFFLE);
+    }
+    assert_eq!(game.outcome(), None);
+    play(&mut game, &SHUFFLE);
+    assert_eq!(
+        game.outcome(),
+        Some(Outcome::Draw(DrawReason::FivefoldRepetition))
+    );
+}
+
+#[test]
+fn fifty_and_seventy_five_move_rules() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 80").unwrap();
+    let mut game = Game::new(position);
+    assert_eq!(game.claimable_draw(), None);
+    play(&mut game, &["a1a2"]);
+    assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+    assert_eq!(game.outcome(), None);
+
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 149 80").unwrap();
+    let mut game = Game::new(position);
+    play(&mut game, &["a1a2"]);
+    assert_eq!(
+        game.outcome(),
+        Some(Outcome::Draw(DrawReason::SeventyFiveMoveRule))
+    );
+}
+
+#[test]
+fn flag_fall_depends_on_whether_any_mate_is_possible() {
+    let timeout = |fen| Outcome::timeout(&Position::from_fen(fen).unwrap(), PieceColor::White);
+    let win = Outcome::Win(PieceColor::Black, WinReason::Timeout);
+    let draw = Outcome::Draw(DrawReason::TimeoutVsInsufficientMaterial);
+
+    // A bare king, or a single minor piece against a bare king, can't mate
+    assert_eq!(timeout("4k3/8/8/8/8/8/3PPP2/4K3 w - - 0 1"), draw);
+    assert_eq!(timeout("4k3/8/8/8/8/8/8/2n1K3 w - - 0 1"), draw);
+    assert_eq!(timeout("4k3/8/8/8/8/8/8/2b1K3 w - - 0 1"), draw);
+    // The flagging side's own pieces can be in the way of its king
+    assert_eq!(timeout("4k3/8/8/8/8/8/3P4/2n1K3 w - - 0 1"), win);
+    assert_eq!(timeout("4k3/8/8/8/8/8/3N4/2b1K3 w - - 0 1"), win);
+    assert_eq!(timeout("4k3/8/8/8/8/8/3R4/2n1K3 w - - 0 1"), win);
+    // but a queen next to the king could take a lone knight
+    assert_eq!(timeout("4k3/8/8/8/8/8/3Q4/2n1K3 w - - 0 1"), draw);
+    // Bishops on squares of one color never cover the other color
+    assert_eq!(timeout("2b1k3/8/8/8/8/8/b7/4K3 w - - 0 1"), draw);
+    assert_eq!(timeout("2b1k3/8/8/8/8/8/b3B3/4K3 w - - 0 1"), draw);
+    assert_eq!(timeout("2b1k3/8/8/8/8/8/b2B4/4K3 w - - 0 1"), win);
+    assert_eq!(timeout("1b2k3/8/8/8/8/8/b7/4K3 w - - 0 1"), win);
+    assert_eq!(timeout("4k3/8/8/8/8/8/8/1nb1K3 w - - 0 1"), win);
+    assert_eq!(timeout("4k3/8/8/8/8/8/8/1nn1K3 w - - 0 1"), win);
+}
+
+fn outcome(fen: &str) -> Option<Outcome> {
+    Game::new(Position::from_fen(fen).unwrap()).outcome()
+}
+
+#[test]
+fn checkmate_and_stalemate_are_told_apart() {
+    // Neither side has a legal move, but only the first king is in check
+    assert_eq!(
+        outcome("k7/1Q6/1K6/8/8/8/8/8 b - - 0 1"),
+        Some(Outcome::Win(PieceColor::White, WinReason::Checkmate))
+    );
+    assert_eq!(
+        outcome("k7/8/1QK5/8/8/8/8/8 b - - 0 1"),
+        Some(Outcome::Draw(DrawReason::Stalemate))
+    );
+    assert_eq!(
+        outcome("7k/8/8/8/8/8/5PPP/r5K1 w - - 0 1"),
+        Some(Outcome::Win(PieceColor::Black, WinReason::Checkmate))
+    );
+    // A king in check that can get out of it, or one that isn't but can move
+    assert_eq!(outcome("k7/8/1K6/8/8/8/8/R7 b - - 0 1"), None);
+    assert_eq!(outcome("k7/8/3Q4/2K5/8/8/8/8 b - - 0 1"), None);
+
+    // The pieces left don't matter: stalemate with mating material is still a draw
+    let position = Position::from_fen("7k/5Q2/6K1/8/8/8/8/R7 b - - 0 1").unwrap();
+    assert!(position.is_stalemate());
+    assert!(!position.is_checkmate());
+    let position = Position::from_fen("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+    assert!(position.is_checkmate());
+    assert!(!position.is_stalemate());
+}
+
+#[test]
+fn insufficient_material_ends_the_game() {
+    let draw = Some(Outcome::Draw(DrawReason::InsufficientMaterial));
+    // King against king
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), draw);
+    // King and bishop or king and knight against king
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), draw);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/1N2K3 b - - 0 1"), draw);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/1n2K3 w - - 0 1"), draw);
+    // Bishops that all stand on squares of one color, whoever they belong to
+    assert_eq!(outcome("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1"), draw);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/B1B1K3 w - - 0 1"), draw);
+
+    // Bishops on both colors, two knights or any pawn, rook or queen can still mate
+    assert_eq!(outcome("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1"), None);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"), None);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/1n2K2N w - - 0 1"), None);
+    assert_eq!(outcome("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"), None);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), None);
+    assert_eq!(outcome("4k3/8/8/8/8/8/8/3QK3 w - - 0 1"), None);
+}
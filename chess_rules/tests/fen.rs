@@ -0,0 +1,129 @@
+use chess_rules::{FenError, Position, START_FEN};
+
+fn placement_error(fen: &str) -> Result<Position, FenError> {
+    Err(FenError::InvalidPlacement(
+        fen.split_whitespace().next().unwrap().to_string(),
+    ))
+}
+
+#[test]
+fn fens_round_trip() {
+    for fen in [
+        START_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3",
+        "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+        "8/2k5/8/8/8/8/5K2/8 b - - 57 112",
+    ]
+    .iter()
+    {
+        assert_eq!(Position::from_fen(fen).unwrap().to_fen(), *fen);
+    }
+    assert_eq!(Position::default().to_fen(), START_FEN);
+}
+
+#[test]
+fn move_counters_are_optional() {
+    let position = Position::from_fen("4k3/8/8/8/8/8/8/4K3 b - -").unwrap();
+    assert_eq!(position.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 0 1");
+}
+
+#[test]
+fn missing_fields_are_errors() {
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w"),
+        Err(FenError::MissingField)
+    );
+    assert_eq!(Position::from_fen(""), Err(FenError::MissingField));
+}
+
+#[test]
+fn invalid_placements_are_errors() {
+    for fen in [
+        // Long runs of digits used to overflow the file
+        "99999999999999999999999999999999/8/8/8/8/8/8/K6k w - - 0 1",
+        "9/8/8/8/8/8/8/K6k w - - 0 1",
+        "08/8/8/8/8/8/8/K6k w - - 0 1",
+        "4k4/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k2p1/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k2/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/8/4K3 w - - 0 1",
+        "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+    ]
+    .iter()
+    {
+        assert_eq!(Position::from_fen(fen), placement_error(fen), "{}", fen);
+    }
+}
+
+#[test]
+fn kings_are_required() {
+    assert_eq!(
+        Position::from_fen("8/8/8/8/8/8/8/4K3 w - - 0 1"),
+        Err(FenError::InvalidKings)
+    );
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/3KK3 w - - 0 1"),
+        Err(FenError::InvalidKings)
+    );
+}
+
+#[test]
+fn invalid_sides_to_move_are_errors() {
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1"),
+        Err(FenError::InvalidSideToMove("x".to_string()))
+    );
+}
+
+#[test]
+fn invalid_castling_rights_are_errors() {
+    assert_eq!(
+        Position::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkx - 0 1"),
+        Err(FenError::InvalidCastling("KQkx".to_string()))
+    );
+}
+
+#[test]
+fn invalid_en_passant_squares_are_errors() {
+    let error = |square: &str| Err(FenError::InvalidEnPassant(square.to_string()));
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 w - e9 0 1"),
+        error("e9")
+    );
+    // White can't take on the third rank, only on the sixth
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/4P3/8/8/4K3 w - e3 0 1"),
+        error("e3")
+    );
+    assert_eq!(
+        Position::from_fen("4k3/8/8/4p3/8/8/8/4K3 b - e6 0 1"),
+        error("e6")
+    );
+    // No pawn just moved past the square
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - e6 0 1"),
+        error("e6")
+    );
+    assert_eq!(
+        Position::from_fen("4k3/8/8/4P3/8/8/8/4K3 w - e6 0 1"),
+        error("e6")
+    );
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/4p3/8/8/4K3 b - e3 0 1"),
+        error("e3")
+    );
+}
+
+#[test]
+fn invalid_counters_are_errors() {
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - x 1"),
+        Err(FenError::InvalidCounter("x".to_string()))
+    );
+    assert_eq!(
+        Position::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 -1"),
+        Err(FenError::InvalidCounter("-1".to_string()))
+    );
+}
@@ -0,0 +1,308 @@
+use chess_rules::{Game, Move, PieceColor, PieceType, Position, Square, START_FEN};
+
+fn mv(s: &str) -> Move {
+    Move::from_uci(s).unwrap()
+}
+
+fn square(name: &str) -> Square {
+    Square::from_algebraic(name).unwrap()
+}
+
+fn position(fen: &str) -> Position {
+    Position::from_fen(fen).unwrap()
+}
+
+#[test]
+fn pawns_promote_to_any_of_four_pieces() {
+    let promotions = [
+        PieceType::Queen,
+        PieceType::Rook,
+        PieceType::Bishop,
+        PieceType::Knight,
+    ];
+    // Pushing to the last rank and taking on it
+    let white = position("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    let moves = white.legal_moves_from(square("b7"));
+    assert_eq!(moves.len(), 8);
+    for &promotion in promotions.iter() {
+        for to in ["a8", "b8"].iter() {
+            let mv = Move {
+                from: square("b7"),
+                to: square(to),
+                promotion: Some(promotion),
+            };
+            assert!(moves.contains(&mv), "{}", mv);
+        }
+    }
+    assert!(!white.is_legal(mv("b7b8")));
+
+    let black = position("4k3/8/8/8/8/8/6p1/4K3 b - - 0 1");
+    let moves = black.legal_moves_from(square("g2"));
+    assert_eq!(moves.len(), 4);
+    assert!(moves.iter().all(|mv| mv.to == square("g1")));
+}
+
+#[test]
+fn underpromotions_round_trip_through_san() {
+    let position = position("r3k3/1P6/8/8/8/8/8/4K3 w - - 0 1");
+    for (uci, san) in [
+        ("b7b8n", "b8=N"),
+        ("b7b8b", "b8=B"),
+        ("b7b8r", "b8=R+"),
+        ("b7a8n", "bxa8=N"),
+    ]
+    .iter()
+    {
+        assert_eq!(position.to_san(mv(uci)), *san);
+        assert_eq!(position.parse_san(san), Ok(mv(uci)));
+    }
+}
+
+#[test]
+fn promotions_can_give_check() {
+    // The knight checks the king on d7, the queen doesn't
+    let position = position("8/1P1k4/8/8/8/8/8/4K3 w - - 0 1");
+    assert_eq!(position.to_san(mv("b7b8n")), "b8=N+");
+    assert_eq!(position.to_san(mv("b7b8q")), "b8=Q");
+    let mut game = Game::new(position);
+    game.play(mv("b7b8n")).unwrap();
+    assert!(game.position().is_check());
+    assert_eq!(
+        game.position().piece_at(square("b8")).unwrap().piece_type,
+        PieceType::Knight
+    );
+    // Every square around the king but c6, which the knight covers
+    assert_eq!(game.position().legal_moves().len(), 7);
+
+    // A queen or a rook mates, a bishop or a knight doesn't even check
+    let position = self::position("k7/2P5/1K6/8/8/8/8/8 w - - 0 1");
+    assert_eq!(position.to_san(mv("c7c8q")), "c8=Q#");
+    assert_eq!(position.to_san(mv("c7c8r")), "c8=R#");
+    assert_eq!(position.to_san(mv("c7c8b")), "c8=B");
+    let mut game = Game::new(position);
+    game.play(mv("c7c8r")).unwrap();
+    assert!(game.position().is_checkmate());
+}
+
+#[test]
+fn en_passant_is_only_possible_right_after_the_double_step() {
+    let mut game = Game::default();
+    for uci in ["e2e4", "a7a6", "e4e5", "d7d5"].iter() {
+        game.play(mv(uci)).unwrap();
+    }
+    assert_eq!(game.position().legal_en_passant(), Some(square("d6")));
+    assert!(game.position().is_legal(mv("e5d6")));
+    assert_eq!(game.position().to_san(mv("e5d6")), "exd6");
+
+    let mut later = game.clone();
+    later.play(mv("e5d6")).unwrap();
+    assert_eq!(later.position().piece_at(square("d5")), None);
+
+    // Once another move is played the chance is gone
+    game.play(mv("h2h3")).unwrap();
+    game.play(mv("h7h6")).unwrap();
+    assert_eq!(game.position().en_passant(), None);
+    assert!(!game.position().is_legal(mv("e5d6")));
+
+    // A pawn that moved one square at a time can't be taken in passing either
+    let mut game = Game::default();
+    for uci in ["e2e4", "d7d6", "e4e5", "d6d5"].iter() {
+        game.play(mv(uci)).unwrap();
+    }
+    assert!(!game.position().is_legal(mv("e5d6")));
+}
+
+#[test]
+fn en_passant_cannot_expose_the_king() {
+    // Both pawns leave the fifth rank, leaving the king to the rook
+    let position = position("8/8/8/K2pP2r/8/8/8/4k3 w - d6 0 1");
+    assert_eq!(position.en_passant(), Some(square("d6")));
+    assert_eq!(position.legal_en_passant(), None);
+    assert!(!position.is_legal(mv("e5d6")));
+
+    // Nor along the diagonal the taking pawn is pinned on
+    let position = self::position("7b/4k3/8/3pP3/8/8/8/K7 w - d6 0 1");
+    assert!(!position.is_legal(mv("e5d6")));
+    assert!(!position.is_legal(mv("e5e6")));
+}
+
+#[test]
+fn en_passant_and_discovered_checks() {
+    // The taken pawn was blocking the bishop
+    let position = position("8/5k2/8/3pP3/8/1B6/8/6K1 w - d6 0 1");
+    assert_eq!(position.to_san(mv("e5d6")), "exd6+");
+    // The taking pawn was blocking the rook
+    let position = self::position("4k3/8/8/3pP3/8/8/8/4R1K1 w - d6 0 1");
+    assert_eq!(position.to_san(mv("e5d6")), "exd6+");
+
+    // Taking the pawn that just gave check gets the king out of it
+    let position = self::position("8/8/8/3pP3/4K3/8/8/7k w - d6 0 1");
+    assert!(position.is_check());
+    assert!(position.is_legal(mv("e5d6")));
+    // but not when the double step discovered a check the capture doesn't block
+    let position = self::position("2b1k3/8/8/3pP3/8/7K/8/8 w - d6 0 1");
+    assert!(position.is_check());
+    assert!(!position.is_legal(mv("e5d6")));
+    assert_eq!(position.legal_en_passant(), None);
+}
+
+#[test]
+fn castling_moves_the_rook_too() {
+    let mut game = Game::new(position("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"));
+    assert_eq!(game.position().to_san(mv("e1g1")), "O-O");
+    game.play(mv("e1g1")).unwrap();
+    assert_eq!(game.position().to_san(mv("e8c8")), "O-O-O");
+    game.play(mv("e8c8")).unwrap();
+    assert_eq!(
+        game.position().to_fen(),
+        "2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2"
+    );
+}
+
+#[test]
+fn castling_is_not_allowed_out_of_check() {
+    let position = position("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1");
+    assert!(position.is_check());
+    assert!(!position.is_legal(mv("e1g1")));
+    assert!(!position.is_legal(mv("e1c1")));
+}
+
+#[test]
+fn castling_is_not_allowed_through_or_into_attacked_squares() {
+    // The king would pass over f1
+    let position = position("4k3/8/8/8/8/5r2/8/R3K2R w KQ - 0 1");
+    assert!(!position.is_legal(mv("e1g1")));
+    assert!(position.is_legal(mv("e1c1")));
+    // or over d1
+    let position = self::position("4k3/8/8/8/8/3r4/8/R3K2R w KQ - 0 1");
+    assert!(position.is_legal(mv("e1g1")));
+    assert!(!position.is_legal(mv("e1c1")));
+    // It would land on g1
+    let position = self::position("4k3/8/8/8/8/6r1/8/R3K2R w KQ - 0 1");
+    assert!(!position.is_legal(mv("e1g1")));
+    // Only the rook goes over b1, so it may be attacked
+    let position = self::position("4k3/8/8/8/8/1r6/8/R3K2R w KQ - 0 1");
+    assert!(position.is_legal(mv("e1c1")));
+    // but it can't be blocked
+    let position = self::position("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1");
+    assert!(!position.is_legal(mv("e1c1")));
+}
+
+#[test]
+fn castling_rights_are_lost_when_the_king_or_a_rook_moves_or_a_rook_is_taken() {
+    let start = position("r3k2r/8/8/8/8/8/6b1/R3K2R b KQkq - 0 1");
+    let rights = |game: &Game| {
+        let rights = game.position().castling_rights();
+        [
+            rights.has(PieceColor::White, true),
+            rights.has(PieceColor::White, false),
+            rights.has(PieceColor::Black, true),
+            rights.has(PieceColor::Black, false),
+        ]
+    };
+
+    // Taking the rook on h1 takes away castling on that side
+    let mut game = Game::new(start.clone());
+    game.play(mv("g2h1")).unwrap();
+    assert_eq!(rights(&game), [false, true, true, true]);
+    assert!(!game.position().is_legal(mv("e1g1")));
+
+    // Moving a rook, even back again
+    let mut game = Game::new(start.clone());
+    for uci in ["a8b8", "a1a2", "b8a8", "a2a1"].iter() {
+        game.play(mv(uci)).unwrap();
+    }
+    assert_eq!(rights(&game), [true, false, true, false]);
+    assert!(!game.position().is_legal(mv("e1c1")));
+
+    // Moving the king
+    let mut game = Game::new(start);
+    game.play(mv("e8d8")).unwrap();
+    assert_eq!(rights(&game), [true, true, false, false]);
+    game.play(mv("e1d2")).unwrap();
+    assert_eq!(rights(&game), [false, false, false, false]);
+}
+
+#[test]
+fn legal_moves_are_those_of_every_piece_to_move() {
+    for fen in [
+        START_FEN,
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    ]
+    .iter()
+    {
+        let position = position(fen);
+        let mut from_squares: Vec<_> = Square::all()
+            .flat_map(|from| position.legal_moves_from(from))
+            .collect();
+        let mut all = position.legal_moves();
+        let key = |mv: &Move| mv.to_string();
+        from_squares.sort_by_key(key);
+        all.sort_by_key(key);
+        assert_eq!(from_squares, all, "{}", fen);
+        assert!(all.iter().all(|&mv| position.is_legal(mv)));
+    }
+}
+
+/// The squares a piece can move to, sorted
+fn destinations(position: &Position, from: &str) -> Vec<String> {
+    let mut squares: Vec<_> = position
+        .legal_moves_from(square(from))
+        .iter()
+        .map(|mv| mv.to.to_string())
+        .collect();
+    squares.sort();
+    squares
+}
+
+#[test]
+fn pinned_pieces_only_move_along_the_pin() {
+    // The rook on e4 is pinned by the rook on e8, the knight on d2 by the bishop on a5
+    let position = position("4r2k/8/8/b7/4R3/8/3N4/4K3 w - - 0 1");
+    assert_eq!(
+        destinations(&position, "e4"),
+        ["e2", "e3", "e5", "e6", "e7", "e8"]
+    );
+    assert!(destinations(&position, "d2").is_empty());
+    // A pinned bishop can't move off the diagonal, and a pinned pawn can still take the pinner
+    let position = self::position("7k/8/8/7b/8/5B2/8/3K4 w - - 0 1");
+    assert_eq!(destinations(&position, "f3"), ["e2", "g4", "h5"]);
+    let position = self::position("7k/8/8/8/8/2b5/3P4/4K3 w - - 0 1");
+    assert_eq!(destinations(&position, "d2"), ["c3"]);
+}
+
+#[test]
+fn in_check_only_moves_that_answer_it_are_legal() {
+    // The queen on e7 checks along the e file
+    let position = position("4k3/4q3/8/8/8/8/3N1B2/R3K3 w - - 0 1");
+    assert!(position.is_check());
+    // Blocking or taking
+    assert_eq!(destinations(&position, "f2"), ["e3"]);
+    assert!(destinations(&position, "a1").is_empty());
+    assert_eq!(destinations(&position, "d2"), ["e4"]);
+    let position = self::position("4k3/4q3/8/3N4/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(destinations(&position, "d5"), ["e3", "e7"]);
+    // or moving the king off the file
+    let position = self::position("4k3/4q3/8/8/8/8/8/R3K3 w - - 0 1");
+    assert_eq!(destinations(&position, "e1"), ["d1", "d2", "f1", "f2"]);
+
+    // In double check only the king may move
+    let position = self::position("4k3/8/8/8/1b6/8/4r3/R3K2R w KQ - 0 1");
+    assert!(position.is_check());
+    assert!(destinations(&position, "a1").is_empty());
+    assert!(destinations(&position, "h1").is_empty());
+    assert_eq!(destinations(&position, "e1"), ["d1", "e2", "f1"]);
+}
+
+#[test]
+fn empty_squares_and_the_other_sides_pieces_have_no_moves() {
+    let position = Position::default();
+    assert!(position.legal_moves_from(square("e4")).is_empty());
+    assert!(position.legal_moves_from(square("e7")).is_empty());
+    assert!(position.legal_moves_from(square("g8")).is_empty());
+    assert_eq!(destinations(&position, "g1"), ["f3", "h3"]);
+    assert!(!position.is_legal(mv("e7e5")));
+}
@@ -0,0 +1,58 @@
+use chess_rules::{Game, GameResult, Move, PgnGame, PieceType, Position, SanError, Square};
+
+fn mv(s: &str) -> Move {
+    Move::from_uci(s).unwrap()
+}
+
+#[test]
+fn typed_moves_accept_san_and_uci() {
+    let position = Position::default();
+    assert_eq!(position.parse_move("e4"), Ok(mv("e2e4")));
+    assert_eq!(position.parse_move("Nf3"), Ok(mv("g1f3")));
+    assert_eq!(position.parse_move("g1f3"), Ok(mv("g1f3")));
+    assert_eq!(position.parse_move(" e2e4 "), Ok(mv("e2e4")));
+}
+
+#[test]
+fn typed_castling_and_promotion() {
+    let position = Position::from_fen("r3k2r/1P6/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+    let castle = position.parse_move("O-O").unwrap();
+    assert_eq!(position.parse_move("e1g1"), Ok(castle));
+
+    let promotion = Move {
+        from: Square::from_algebraic("b7").unwrap(),
+        to: Square::from_algebraic("b8").unwrap(),
+        promotion: Some(PieceType::Queen),
+    };
+    assert_eq!(position.parse_move("b8=Q"), Ok(promotion));
+    assert_eq!(position.parse_move("b7b8q"), Ok(promotion));
+}
+
+#[test]
+fn typed_moves_report_errors() {
+    let position = Position::default();
+    assert_eq!(
+        position.parse_move("e5"),
+        Err(SanError::Illegal("e5".to_string()))
+    );
+    assert_eq!(
+        position.parse_move("hello"),
+        Err(SanError::Invalid("hello".to_string()))
+    );
+
+    let knights = Position::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+    assert_eq!(
+        knights.parse_move("Nd2"),
+        Err(SanError::Ambiguous("Nd2".to_string()))
+    );
+    assert!(knights.parse_move("Nbd2").is_ok());
+}
+
+#[test]
+fn unknown_tags_use_the_pgn_placeholders() {
+    let pgn = PgnGame::new(Game::default(), GameResult::Unknown);
+    assert_eq!(pgn.tag("Event"), Some("?"));
+    assert_eq!(pgn.tag("Date"), Some("????.??.??"));
+    assert_eq!(pgn.tag("Result"), Some("*"));
+    assert!(pgn.to_string().contains("[Date \"????.??.??\"]"));
+}
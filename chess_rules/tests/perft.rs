@@ -0,0 +1,126 @@
+//! Move generation checked against the node counts of well known perft positions, see
+//! https://www.chessprogramming.org/Perft_Results
+
+use chess_rules::{divide, perft, PieceColor, PieceType, Position, START_FEN};
+
+fn check(fen: &str, counts: &[u64]) {
+    let position = Position::from_fen(fen).unwrap();
+    for (depth, &count) in (1..).zip(counts) {
+        assert_eq!(perft(&position, depth), count, "depth {} of {}", depth, fen);
+    }
+}
+
+#[test]
+fn start_position() {
+    check(START_FEN, &[20, 400, 8902, 197_281]);
+}
+
+#[test]
+fn kiwipete() {
+    // Castling on both sides, pins, en passant and promotions all at once
+    check(
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        &[48, 2039, 97_862],
+    );
+}
+
+#[test]
+fn en_passant_and_checks_along_ranks() {
+    check(
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        &[14, 191, 2812, 43_238],
+    );
+}
+
+#[test]
+fn promotions_and_castling_rights() {
+    check(
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        &[6, 264, 9467],
+    );
+    // The same position with the colors swapped
+    check(
+        "r2q1rk1/pP1p2pp/Q4n2/bbp1p3/Np6/1B3NBn/pPPP1PPP/R3K2R b KQ - 0 1",
+        &[6, 264, 9467],
+    );
+}
+
+#[test]
+fn promotion_with_check() {
+    check(
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        &[44, 1486, 62_379],
+    );
+}
+
+#[test]
+fn quiet_middlegame() {
+    check(
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        &[46, 2079, 89_890],
+    );
+}
+
+#[test]
+fn chess960_castling() {
+    check(
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+        &[21, 528, 12_189],
+    );
+}
+
+#[test]
+fn divide_adds_up_to_perft() {
+    let position = Position::default();
+    let moves = divide(&position, 3);
+    assert_eq!(moves.len(), 20);
+    let total: u64 = moves.iter().map(|(_, count)| count).sum();
+    assert_eq!(total, perft(&position, 3));
+}
+
+#[test]
+fn unmake_restores_the_position() {
+    // Captures, en passant, promotions and castling, in standard chess and Chess960
+    for fen in [
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+    ]
+    .iter()
+    {
+        let original = Position::from_fen(fen).unwrap();
+        let mut position = original.clone();
+        for mv in original.legal_moves() {
+            let undo = position.make_move(mv);
+            for reply in position.legal_moves() {
+                let after = position.clone();
+                let reply_undo = position.make_move(reply);
+                position.unmake_move(reply, reply_undo);
+                assert_eq!(position, after, "{} {} of {}", mv, reply, fen);
+            }
+            position.unmake_move(mv, undo);
+            assert_eq!(position, original, "{} of {}", mv, fen);
+        }
+    }
+}
+
+#[test]
+fn bitboards_match_the_pieces() {
+    let position =
+        Position::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1")
+            .unwrap();
+    assert_eq!(position.occupied().count(), 32);
+    for (square, piece) in position.pieces() {
+        assert!(position
+            .bitboard(piece.color, piece.piece_type)
+            .contains(square));
+    }
+    let white_pawns: Vec<_> = position
+        .bitboard(PieceColor::White, PieceType::Pawn)
+        .map(|square| square.to_string())
+        .collect();
+    assert_eq!(
+        white_pawns,
+        ["a2", "b2", "c2", "f2", "g2", "h2", "e4", "d5"]
+    );
+}
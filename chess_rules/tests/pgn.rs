@@ -0,0 +1,146 @@
+use chess_rules::{Game, GameResult, PgnError, PgnGame, PieceColor, Position, SanError};
+
+fn san_moves(pgn: &PgnGame) -> Vec<String> {
+    let positions = pgn.game.positions();
+    pgn.game
+        .moves()
+        .iter()
+        .enumerate()
+        .map(|(i, &mv)| positions[i].to_san(mv))
+        .collect()
+}
+
+#[test]
+fn every_game_in_a_file_is_read() {
+    let pgn = r#"[Event "First"]
+[Result "1-0"]
+
+1. e4 e5 2. Qh5 Nc6 3. Bc4 Nf6 4. Qxf7# 1-0
+
+[Event "Second"]
+[Result "1/2-1/2"]
+
+1.d4 d5 2.c4 1/2-1/2
+
+[Event "Unfinished"]
+
+1. Nf3 *
+"#;
+    let games = PgnGame::parse_all(pgn).unwrap();
+    assert_eq!(games.len(), 3);
+
+    assert_eq!(games[0].tag("Event"), Some("First"));
+    assert_eq!(games[0].result, GameResult::WhiteWins);
+    assert_eq!(games[0].game.moves().len(), 7);
+    assert!(games[0].game.outcome().is_some());
+
+    assert_eq!(games[1].tag("Event"), Some("Second"));
+    assert_eq!(games[1].result, GameResult::Draw);
+    assert_eq!(san_moves(&games[1]), ["d4", "d5", "c4"]);
+
+    assert_eq!(games[2].tag("Event"), Some("Unfinished"));
+    assert_eq!(games[2].result, GameResult::Unknown);
+    assert_eq!(san_moves(&games[2]), ["Nf3"]);
+}
+
+#[test]
+fn comments_glyphs_and_variations_are_skipped() {
+    let pgn = "% an escaped line, 1. d4\n\
+        1. e4 {the best by test, 1. d4 is fine too} e5 $1 2. Nf3 (2. f4 exf4 (2... d5 3. exd5) \
+        3. Nf3) 2... Nc6 ; 2... d6 is Philidor's\n\
+        3. Bb5!? a6 $6 *";
+    let games = PgnGame::parse_all(pgn).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(
+        san_moves(&games[0]),
+        ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]
+    );
+}
+
+#[test]
+fn fen_tags_set_the_start_position() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 b - - 0 40";
+    let pgn = format!("[SetUp \"1\"]\n[FEN \"{}\"]\n\n40... Kd7 41. e4 Kd6 *", fen);
+    let games = PgnGame::parse_all(&pgn).unwrap();
+    assert_eq!(
+        games[0].game.start_position(),
+        &Position::from_fen(fen).unwrap()
+    );
+    assert_eq!(san_moves(&games[0]), ["Kd7", "e4", "Kd6"]);
+
+    assert!(matches!(
+        PgnGame::parse_all("[SetUp \"1\"]\n[FEN \"8/8/8 w - - 0 1\"]\n\n1. e4 *"),
+        Err(PgnError::InvalidFen { game: 1, .. })
+    ));
+}
+
+#[test]
+fn written_games_are_read_back() {
+    let mut game = Game::new(Position::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 40").unwrap());
+    for san in ["Kd7", "e4", "Kd6", "Kd2"].iter() {
+        let mv = game.position().parse_san(san).unwrap();
+        game.play(mv).unwrap();
+    }
+    let written = PgnGame::new(game, GameResult::Draw).to_string();
+    let games = PgnGame::parse_all(&written).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(san_moves(&games[0]), ["Kd7", "e4", "Kd6", "Kd2"]);
+    assert_eq!(games[0].result, GameResult::Draw);
+}
+
+#[test]
+fn illegal_moves_are_reported_with_the_game_and_move() {
+    let pgn = "1. e4 e5 *\n\n1. e4 e5 2. Ke3 *";
+    let error = PgnGame::parse_all(pgn).unwrap_err();
+    assert_eq!(
+        error,
+        PgnError::InvalidMove {
+            game: 2,
+            move_number: 2,
+            color: PieceColor::White,
+            error: SanError::Illegal("Ke3".to_string()),
+        }
+    );
+    assert_eq!(
+        error.to_string(),
+        "game 2, move 2.: Ke3 is not a legal move"
+    );
+}
+
+#[test]
+fn ambiguous_moves_are_reported_with_the_game_and_move() {
+    let pgn = "[FEN \"1n2kn2/8/8/8/8/8/8/4K3 w - - 0 1\"]\n\n1. Kd2 Nd7 *";
+    let error = PgnGame::parse_all(pgn).unwrap_err();
+    assert_eq!(
+        error,
+        PgnError::InvalidMove {
+            game: 1,
+            move_number: 1,
+            color: PieceColor::Black,
+            error: SanError::Ambiguous("Nd7".to_string()),
+        }
+    );
+    assert_eq!(error.to_string(), "game 1, move 1...: Nd7 is ambiguous");
+}
+
+#[test]
+fn unbalanced_variations_are_errors() {
+    for pgn in [
+        "1. e4 ) e5 2. Nf3 *",
+        "1. e4 (1. d4 d5) e5 2. Nf3 ) *",
+        "1. e4 (1. d4 d5 2. c4 *",
+    ]
+    .iter()
+    {
+        assert_eq!(
+            PgnGame::parse_all(pgn).unwrap_err(),
+            PgnError::UnbalancedVariation { game: 1 },
+            "{}",
+            pgn
+        );
+    }
+    assert_eq!(
+        PgnGame::parse_all("1. e4 *\n\n1. d4 ) *").unwrap_err(),
+        PgnError::UnbalancedVariation { game: 2 }
+    );
+}
@@ -0,0 +1,76 @@
+use chess_rules::{Game, Position, Search, SearchInfo, SearchLimits};
+
+/// Searches the position to the depth, returning the move found and the last info
+fn search_to_depth(search: &mut Search, game: &Game, depth: u32) -> (String, SearchInfo) {
+    search.set_limits(SearchLimits {
+        depth: Some(depth),
+        time: None,
+    });
+    let mut last = None;
+    let best_move = search.run(game, |info| last = Some(info.clone())).unwrap();
+    (best_move.to_string(), last.unwrap())
+}
+
+#[test]
+fn the_menu_puzzles_are_solved() {
+    // The puzzles of the main menu, with the number of moves to mate
+    for &(fen, moves) in [
+        ("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", 1),
+        (
+            "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+            1,
+        ),
+        ("6rk/6pp/8/6N1/8/8/8/6QK w - - 0 1", 1),
+        (
+            "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 10",
+            2,
+        ),
+        (
+            "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1",
+            3,
+        ),
+    ]
+    .iter()
+    {
+        let mut game = Game::new(Position::from_fen(fen).unwrap());
+        let (best_move, info) =
+            search_to_depth(&mut Search::new(SearchLimits::default()), &game, moves * 2);
+        assert_eq!(info.mate_in(), Some(moves as i32), "{}", fen);
+        assert_eq!(info.pv[0].to_string(), best_move);
+        // The line found ends in mate
+        for &mv in &info.pv {
+            game.play(mv).unwrap();
+        }
+        assert_eq!(game.moves().len() as u32, moves * 2 - 1);
+        assert!(game.position().is_checkmate(), "{}", fen);
+    }
+}
+
+#[test]
+fn the_table_carries_over_from_one_run_to_the_next() {
+    let game = Game::new(
+        Position::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4")
+            .unwrap(),
+    );
+    let mut search = Search::new(SearchLimits::default());
+    let (first_move, first) = search_to_depth(&mut search, &game, 5);
+    // What the first run found makes the second one cheaper
+    let (second_move, second) = search_to_depth(&mut search, &game, 5);
+    assert!(
+        second.nodes < first.nodes,
+        "{} {}",
+        first.nodes,
+        second.nodes
+    );
+    assert_eq!(second_move, first_move);
+
+    // but not once the table is cleared
+    search.clear_table();
+    let (_, cleared) = search_to_depth(&mut search, &game, 5);
+    assert!(
+        cleared.nodes > second.nodes,
+        "{} {}",
+        cleared.nodes,
+        second.nodes
+    );
+}
@@ -0,0 +1,42 @@
+//! A stand-in UCI engine for tests. It answers each `go` with the next move given
+//! on its command line, whatever the position, and with `bestmove (none)` once
+//! it runs out of them. A `stall` in place of a move makes it keep thinking
+//! until it's told to stop, and then answer with the move after it.
+
+use std::{
+    env,
+    io::{self, BufRead, Write},
+};
+
+fn main() {
+    let mut script = env::args().skip(1);
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.unwrap();
+        match line.split_whitespace().next() {
+            Some("uci") => {
+                writeln!(stdout, "id name Scripted engine").unwrap();
+                writeln!(stdout, "id author bevy_chess").unwrap();
+                writeln!(stdout, "uciok").unwrap();
+            }
+            Some("isready") => writeln!(stdout, "readyok").unwrap(),
+            Some("go") => match script.next() {
+                Some(step) if step == "stall" => {}
+                Some(mv) => {
+                    writeln!(stdout, "info depth 1 score cp 13 nodes 1 time 0 pv {}", mv).unwrap();
+                    writeln!(stdout, "bestmove {}", mv).unwrap();
+                }
+                None => writeln!(stdout, "bestmove (none)").unwrap(),
+            },
+            Some("stop") => {
+                if let Some(mv) = script.next() {
+                    writeln!(stdout, "bestmove {}", mv).unwrap();
+                }
+            }
+            Some("quit") => break,
+            _ => {}
+        }
+        stdout.flush().unwrap();
+    }
+}
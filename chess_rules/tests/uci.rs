@@ -0,0 +1,199 @@
+use std::{process::Command, time::Duration};
+
+use chess_rules::{
+    parse_go, parse_position, position_command, Game, GoLimits, Move, PieceColor, Position, Square,
+    UciEngine, UciError, UciInfo, UciScore,
+};
+
+fn scripted_engine(script: &[&str]) -> UciEngine {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_scripted_engine"));
+    command.args(script);
+    UciEngine::spawn_command(command).unwrap()
+}
+
+fn e2e4() -> Move {
+    Move::new(
+        Square::from_algebraic("e2").unwrap(),
+        Square::from_algebraic("e4").unwrap(),
+    )
+}
+
+#[test]
+fn handshake_reads_the_engine_name() {
+    let engine = scripted_engine(&[]);
+    assert_eq!(engine.name(), Some("Scripted engine"));
+}
+
+#[test]
+fn go_returns_the_best_move_and_reports_info() {
+    let mut engine = scripted_engine(&["e2e4", "g1f3"]);
+    let game = Game::default();
+    let mut infos = Vec::new();
+    let best_move = engine
+        .go(
+            &game,
+            GoLimits::MoveTime(Duration::from_millis(100)),
+            |info| infos.push(info.clone()),
+        )
+        .unwrap();
+    assert_eq!(best_move, Some(e2e4()));
+    assert_eq!(infos.len(), 1);
+    assert_eq!(infos[0].depth, Some(1));
+    assert_eq!(infos[0].score, Some(UciScore::Centipawns(13)));
+    assert_eq!(infos[0].pv, vec![e2e4()]);
+
+    let best_move = engine.go(&game, GoLimits::Depth(1), |_| {}).unwrap();
+    assert_eq!(best_move, Move::from_uci("g1f3"));
+    let best_move = engine.go(&game, GoLimits::Depth(1), |_| {}).unwrap();
+    assert_eq!(best_move, None);
+}
+
+#[test]
+fn engines_past_their_time_are_told_to_stop() {
+    let mut engine = scripted_engine(&["stall", "d2d4"]);
+    let best_move = engine
+        .go(
+            &Game::default(),
+            GoLimits::MoveTime(Duration::from_millis(100)),
+            |_| {},
+        )
+        .unwrap();
+    assert_eq!(best_move, Move::from_uci("d2d4"));
+}
+
+#[test]
+fn invalid_best_moves_are_errors() {
+    let mut engine = scripted_engine(&["e2e9"]);
+    match engine.go(&Game::default(), GoLimits::Depth(1), |_| {}) {
+        Err(UciError::InvalidBestMove(s)) => assert_eq!(s, "e2e9"),
+        other => panic!("unexpected answer: {:?}", other),
+    }
+}
+
+#[test]
+fn missing_engines_fail_to_spawn() {
+    assert!(matches!(
+        UciEngine::spawn("./there-is-no-such-engine"),
+        Err(UciError::Io(_))
+    ));
+}
+
+#[test]
+fn position_command_lists_the_moves() {
+    let mut game = Game::default();
+    assert_eq!(position_command(&game), "position startpos");
+    game.play(e2e4()).unwrap();
+    assert_eq!(position_command(&game), "position startpos moves e2e4");
+
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let mut game = Game::new(Position::from_fen(fen).unwrap());
+    game.play(e2e4()).unwrap();
+    assert_eq!(
+        position_command(&game),
+        format!("position fen {} moves e2e4", fen)
+    );
+}
+
+/// The arguments of a command, as an engine gets them after the command's name
+fn args(line: &str) -> Vec<&str> {
+    line.split_whitespace().skip(1).collect()
+}
+
+#[test]
+fn position_startpos_plays_the_moves() {
+    let line = "position startpos moves e2e4 e7e5 g1f3";
+    let game = parse_position(&args(line), false).unwrap();
+    assert_eq!(game.start_position(), &Position::default());
+    assert_eq!(game.moves().len(), 3);
+    assert_eq!(position_command(&game), line);
+
+    let game = parse_position(&args("position startpos"), false).unwrap();
+    assert_eq!(game.position(), &Position::default());
+}
+
+#[test]
+fn position_fen_plays_the_moves() {
+    let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+    let line = format!("position fen {} moves e2e4 e8d7", fen);
+    let game = parse_position(&args(&line), false).unwrap();
+    assert_eq!(game.start_position(), &Position::from_fen(fen).unwrap());
+    assert_eq!(game.position().to_fen(), "8/3k4/8/8/4P3/8/8/4K3 w - - 1 2");
+    assert_eq!(position_command(&game), line);
+}
+
+#[test]
+fn invalid_positions_are_errors() {
+    assert!(parse_position(&args("position"), false).is_err());
+    assert!(parse_position(&args("position fen 8/8/8 w - - 0 1"), false).is_err());
+    assert!(parse_position(&args("position startpos moves e2e5"), false).is_err());
+    assert!(parse_position(&args("position startpos moves e2"), false).is_err());
+}
+
+#[test]
+fn go_depth_and_movetime_are_limits() {
+    let (limits, infinite) = parse_go(&args("go depth 6"), PieceColor::White);
+    assert_eq!(limits.depth, Some(6));
+    assert_eq!(limits.time, None);
+    assert!(!infinite);
+
+    let (limits, infinite) = parse_go(&args("go movetime 1500"), PieceColor::Black);
+    assert_eq!(limits.depth, None);
+    assert_eq!(limits.time, Some(Duration::from_millis(1500)));
+    assert!(!infinite);
+}
+
+#[test]
+fn go_with_clocks_uses_the_side_to_moves_time() {
+    let line = "go wtime 60000 btime 30000 winc 2000 binc 0";
+    let (limits, infinite) = parse_go(&args(line), PieceColor::White);
+    assert_eq!(limits.time, Some(Duration::from_millis(2000 + 1500)));
+    assert!(!infinite);
+    let (limits, _) = parse_go(&args(line), PieceColor::Black);
+    assert_eq!(limits.time, Some(Duration::from_millis(1000)));
+
+    // The time is spread over the moves until the next time control
+    let line = "go wtime 60000 btime 60000 movestogo 10";
+    let (limits, _) = parse_go(&args(line), PieceColor::White);
+    assert_eq!(limits.time, Some(Duration::from_millis(6000)));
+
+    // Some time is always left for the GUI, but the engine still gets to move
+    let line = "go wtime 1000 btime 1000 movestogo 1";
+    let (limits, _) = parse_go(&args(line), PieceColor::White);
+    assert_eq!(limits.time, Some(Duration::from_millis(950)));
+    let (limits, _) = parse_go(&args("go wtime 30 btime 30"), PieceColor::White);
+    assert_eq!(limits.time, Some(Duration::from_millis(10)));
+}
+
+#[test]
+fn go_without_limits_is_infinite() {
+    let (limits, infinite) = parse_go(&args("go infinite"), PieceColor::White);
+    assert_eq!(limits.depth, None);
+    assert_eq!(limits.time, None);
+    assert!(infinite);
+    assert!(parse_go(&args("go"), PieceColor::White).1);
+    assert!(parse_go(&args("go depth 3 infinite"), PieceColor::White).1);
+}
+
+#[test]
+fn info_lines_are_parsed() {
+    let info =
+        UciInfo::parse("info depth 12 seldepth 18 score mate -3 nodes 4521 time 250 pv e2e4 e7e5")
+            .unwrap();
+    assert_eq!(info.depth, Some(12));
+    assert_eq!(info.score, Some(UciScore::Mate(-3)));
+    assert_eq!(info.nodes, Some(4521));
+    assert_eq!(info.time, Some(Duration::from_millis(250)));
+    assert_eq!(info.pv.len(), 2);
+
+    assert_eq!(
+        UciInfo::parse("info string hello pv e2e4").unwrap().pv,
+        vec![]
+    );
+    assert_eq!(UciInfo::parse("bestmove e2e4"), None);
+}
+
+#[test]
+fn info_lines_round_trip() {
+    let line = "info depth 3 score cp -25 nodes 1200 time 15 pv e2e4 e7e5 g1f3";
+    assert_eq!(UciInfo::parse(line).unwrap().to_string(), line);
+}
@@ -0,0 +1,100 @@
+use chess_rules::{Bound, Game, Move, Position, Square, TranspositionTable, TtEntry};
+
+fn play(game: &mut Game, moves: &[&str]) {
+    for mv in moves {
+        game.play(Move::from_uci(mv).unwrap()).unwrap();
+    }
+}
+
+fn key(fen: &str) -> u64 {
+    Position::from_fen(fen).unwrap().zobrist_key()
+}
+
+#[test]
+fn keys_are_kept_up_to_date_by_moves() {
+    let mut game = Game::default();
+    play(
+        &mut game,
+        &[
+            "e2e4", "d7d5", "e4d5", "g8f6", "f1b5", "c7c6", "d5c6", "d8a5", "c6b7", "a5b5",
+            "b7a8q", "e8d8", "g1f3", "b5b2", "e1g1",
+        ],
+    );
+    for position in game.positions() {
+        assert_eq!(
+            position.zobrist_key(),
+            key(&position.to_fen()),
+            "{}",
+            position.to_fen()
+        );
+    }
+}
+
+#[test]
+fn transpositions_have_the_same_key() {
+    let mut first = Game::default();
+    play(&mut first, &["g1f3", "g8f6", "b1c3"]);
+    let mut second = Game::default();
+    play(&mut second, &["b1c3", "g8f6", "g1f3"]);
+    assert_eq!(
+        first.position().zobrist_key(),
+        second.position().zobrist_key()
+    );
+}
+
+#[test]
+fn side_to_move_and_castling_rights_change_the_key() {
+    let start = key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_ne!(
+        start,
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1")
+    );
+    assert_ne!(
+        start,
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1")
+    );
+    // The counters don't count for repetitions
+    assert_eq!(
+        start,
+        key("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 5 9")
+    );
+}
+
+#[test]
+fn en_passant_counts_only_when_it_can_be_taken() {
+    // No black pawn can take on e3
+    assert_eq!(
+        key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        key("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"),
+    );
+    // The pawn on d4 can
+    assert_ne!(
+        key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"),
+        key("rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1"),
+    );
+}
+
+#[test]
+fn table_keeps_the_deeper_entry_of_a_position() {
+    let mut table = TranspositionTable::new(1);
+    let entry = |key, depth| TtEntry {
+        key,
+        depth,
+        bound: Bound::Exact,
+        score: 0,
+        best_move: Some(Move::new(Square::new(4, 1), Square::new(4, 3))),
+    };
+    assert_eq!(table.get(42), None);
+    table.insert(entry(42, 5));
+    table.insert(entry(42, 3));
+    assert_eq!(table.get(42), Some(entry(42, 5)));
+
+    // Another position in the same slot takes its place
+    let other = 42 + table.capacity() as u64;
+    table.insert(entry(other, 1));
+    assert_eq!(table.get(42), None);
+    assert_eq!(table.get(other), Some(entry(other, 1)));
+
+    table.clear();
+    assert_eq!(table.get(other), None);
+}
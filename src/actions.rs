@@ -0,0 +1,365 @@
+use bevy::prelude::*;
+use chess_rules::{evaluate, Position, UciScore, MATE_SCORE};
+
+use crate::{
+    ai::{AiAnalysis, AiPlayer},
+    board::GameStatus,
+    camera::viewing_side,
+    move_input::MoveInput,
+    pieces::PieceColor,
+    replay::Replay,
+    state::{AppState, GameEntity, ScreenMaterials},
+};
+
+/// What a player can do besides moving, with the buttons below the status or the keys
+#[derive(Clone, Copy)]
+pub enum GameAction {
+    /// R
+    Resign,
+    /// D, which also accepts the draw the other side offered
+    OfferDraw,
+    /// C, under the threefold repetition or fifty-move rule
+    ClaimDraw,
+    AcceptDraw,
+    DeclineDraw,
+}
+
+/// A draw offered by one side, which stands until the other side answers it or plays a move
+#[derive(Default)]
+pub struct DrawOffer {
+    /// The side that offered it
+    by: Option<PieceColor>,
+    /// How many moves had been played when it was offered, or the notice given
+    moves: usize,
+    /// Why the last offer or claim didn't end the game, shown until the next move
+    notice: Option<String>,
+}
+
+/// The computer accepts a draw once it's at least this far behind, in centipawns
+const ACCEPT_DRAW_SCORE: i32 = -50;
+
+// Component to mark the Text entity showing the draw offer or notice
+struct DrawOfferText;
+
+// Component to mark the node holding the buttons answering a draw offer
+struct DrawOfferButtons;
+
+fn color_name(color: PieceColor) -> &'static str {
+    match color {
+        PieceColor::White => "White",
+        PieceColor::Black => "Black",
+    }
+}
+
+fn spawn_action_button(
+    parent: &mut ChildBuilder,
+    label: &str,
+    action: GameAction,
+    font: &Handle<Font>,
+    materials: &ScreenMaterials,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(130.), Val::Px(30.)),
+                margin: Rect::all(Val::Px(2.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    label,
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+        });
+}
+
+/// Initialize the action buttons on the left, below the status and material texts
+fn init_actions(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<ScreenMaterials>,
+    replay: Option<Res<Replay>>,
+) {
+    // A replayed game is already over
+    if replay.is_some() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    top: Val::Px(85.),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: materials.transparent.clone(),
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            spawn_action_button(parent, "Resign", GameAction::Resign, &font, &materials);
+            spawn_action_button(
+                parent,
+                "Offer draw",
+                GameAction::OfferDraw,
+                &font,
+                &materials,
+            );
+            spawn_action_button(
+                parent,
+                "Claim draw",
+                GameAction::ClaimDraw,
+                &font,
+                &materials,
+            );
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(DrawOfferText);
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        display: Display::None,
+                        flex_direction: FlexDirection::ColumnReverse,
+                        ..Default::default()
+                    },
+                    material: materials.transparent.clone(),
+                    ..Default::default()
+                })
+                .insert(DrawOfferButtons)
+                .with_children(|parent| {
+                    spawn_action_button(
+                        parent,
+                        "Accept",
+                        GameAction::AcceptDraw,
+                        &font,
+                        &materials,
+                    );
+                    spawn_action_button(
+                        parent,
+                        "Decline",
+                        GameAction::DeclineDraw,
+                        &font,
+                        &materials,
+                    );
+                });
+        });
+}
+
+/// A new game starts without any offer
+fn reset_draw_offer(mut draw_offer: ResMut<DrawOffer>) {
+    *draw_offer = DrawOffer::default();
+}
+
+fn action_keys(
+    keyboard_input: Res<Input<KeyCode>>,
+    move_input: Res<MoveInput>,
+    mut action_event: EventWriter<GameAction>,
+) {
+    if move_input.focused {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::R) {
+        action_event.send(GameAction::Resign);
+    } else if keyboard_input.just_pressed(KeyCode::D) {
+        action_event.send(GameAction::OfferDraw);
+    } else if keyboard_input.just_pressed(KeyCode::C) {
+        action_event.send(GameAction::ClaimDraw);
+    }
+}
+
+fn action_buttons(
+    materials: Res<ScreenMaterials>,
+    mut action_event: EventWriter<GameAction>,
+    mut interaction_query: Query<
+        (&Interaction, &GameAction, &mut Handle<ColorMaterial>),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, action, mut material) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => action_event.send(*action),
+            Interaction::Hovered => *material = materials.hovered.clone(),
+            Interaction::None => *material = materials.normal.clone(),
+        }
+    }
+}
+
+/// How the computer sees its chances, in centipawns: from its last search if there
+/// was one, or else from a quick look at the position
+fn ai_score(analysis: &AiAnalysis, position: &Position, ai_color: PieceColor) -> i32 {
+    match analysis.0.as_ref().and_then(|info| info.score) {
+        Some(UciScore::Centipawns(score)) => score,
+        Some(UciScore::Mate(moves)) if moves > 0 => MATE_SCORE,
+        Some(UciScore::Mate(_)) => -MATE_SCORE,
+        None if position.side_to_move() == ai_color => evaluate(position),
+        None => -evaluate(position),
+    }
+}
+
+/// Resigns, offers, answers or claims a draw for the player at the board: the side to
+/// move in hot-seat games, and the side facing the computer otherwise
+fn handle_actions(
+    mut action_events: EventReader<GameAction>,
+    mut game_status: ResMut<GameStatus>,
+    mut draw_offer: ResMut<DrawOffer>,
+    ai_player: Res<AiPlayer>,
+    analysis: Res<AiAnalysis>,
+    replay: Option<Res<Replay>>,
+) {
+    for &action in action_events.iter() {
+        if replay.is_some() || game_status.is_over() {
+            return;
+        }
+        let player = viewing_side(&ai_player, &game_status);
+        let moves = game_status.game.moves().len();
+        let mut notice = None;
+        match action {
+            GameAction::Resign => game_status.resign(player),
+            // Only the other side can answer an offer
+            GameAction::AcceptDraw if draw_offer.by == Some(player.other()) => {
+                game_status.agree_draw()
+            }
+            // Offering back is accepting, once the side that offered has played its move
+            GameAction::OfferDraw if draw_offer.by.is_some() => {
+                if draw_offer.by != Some(player) {
+                    game_status.agree_draw();
+                }
+            }
+            GameAction::OfferDraw => match ai_player.color {
+                // The computer answers right away
+                Some(ai_color) => {
+                    let position = game_status.game.position();
+                    if ai_score(&analysis, position, ai_color) <= ACCEPT_DRAW_SCORE {
+                        game_status.agree_draw();
+                    } else {
+                        notice = Some(format!("{} declines the draw", color_name(ai_color)));
+                    }
+                }
+                None => {
+                    draw_offer.by = Some(player);
+                    draw_offer.moves = moves;
+                }
+            },
+            GameAction::AcceptDraw => {}
+            GameAction::DeclineDraw => {
+                if draw_offer.by == Some(player.other()) {
+                    draw_offer.by = None;
+                    notice = Some(format!("{} declines the draw", color_name(player)));
+                }
+            }
+            GameAction::ClaimDraw => {
+                if player != game_status.color {
+                    notice = Some("Claim on your turn".to_string());
+                } else if !game_status.claim_draw() {
+                    notice = Some("No draw to claim".to_string());
+                }
+            }
+        }
+        if notice.is_some() {
+            draw_offer.notice = notice;
+            draw_offer.moves = moves;
+        }
+    }
+}
+
+/// An offer lapses once the other side plays a move instead of answering it, or when
+/// moves are taken back past it, and notices go away with the next move
+fn expire_draw_offer(game_status: Res<GameStatus>, mut draw_offer: ResMut<DrawOffer>) {
+    let game = &game_status.game;
+    if !game_status.is_changed() || game.moves().len() == draw_offer.moves {
+        return;
+    }
+    if let Some(color) = draw_offer.by {
+        let answered = game.moves().len() < draw_offer.moves
+            || game.positions()[draw_offer.moves..game.moves().len()]
+                .iter()
+                .any(|position| position.side_to_move() != color);
+        if answered {
+            draw_offer.by = None;
+        }
+    }
+    draw_offer.notice = None;
+}
+
+/// Show the standing offer, with the buttons to answer it to the other side, or the last notice
+fn update_draw_offer(
+    draw_offer: Res<DrawOffer>,
+    game_status: Res<GameStatus>,
+    ai_player: Res<AiPlayer>,
+    mut text_query: Query<&mut Text, With<DrawOfferText>>,
+    mut buttons_query: Query<&mut Style, With<DrawOfferButtons>>,
+) {
+    if !draw_offer.is_changed() && !game_status.is_changed() {
+        return;
+    }
+    let offered = draw_offer.by.filter(|_| !game_status.is_over());
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = match offered {
+            Some(color) => format!("{} offers a draw", color_name(color)),
+            None => draw_offer.notice.clone().unwrap_or_default(),
+        };
+    }
+    let player = viewing_side(&ai_player, &game_status);
+    for mut style in buttons_query.iter_mut() {
+        style.display = if offered == Some(player.other()) {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+pub struct ActionsPlugin;
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<DrawOffer>()
+            .add_event::<GameAction>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(init_actions.system())
+                    .with_system(reset_draw_offer.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(action_keys.system().label("game_actions"))
+                    .with_system(action_buttons.system().label("game_actions"))
+                    .with_system(handle_actions.system().after("game_actions"))
+                    .with_system(expire_draw_offer.system()),
+            )
+            .add_system_set(
+                SystemSet::on_in_stack_update(AppState::InGame)
+                    .with_system(update_draw_offer.system()),
+            );
+    }
+}
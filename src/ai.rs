@@ -0,0 +1,252 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+use chess_rules::{Clock, GoLimits, Move, Position, Search, SearchLimits, TimeBonus, UciInfo};
+
+use crate::{
+    board::{GameStatus, StatusType},
+    book::{self, OpeningBook},
+    clock::GameClock,
+    engine::ExternalEngine,
+    pieces::{DisplayedPosition, PieceColor},
+    state::AppState,
+};
+
+/// Which side, if any, is played by the computer
+pub struct AiPlayer {
+    pub color: Option<PieceColor>,
+    /// Whether the external engine plays instead of the built-in one
+    pub external: bool,
+    /// How long the computer thinks about each move when there's no clock
+    pub think_time: Duration,
+}
+impl Default for AiPlayer {
+    fn default() -> Self {
+        AiPlayer {
+            color: None,
+            external: false,
+            think_time: Duration::from_secs(2),
+        }
+    }
+}
+impl AiPlayer {
+    pub fn plays(&self, color: PieceColor) -> bool {
+        self.color == Some(color)
+    }
+}
+
+/// What the computer last reported about its search
+#[derive(Default)]
+pub struct AiAnalysis(pub Option<UciInfo>);
+
+enum AiMessage {
+    Info(UciInfo),
+    BestMove(Move),
+    /// Why the computer has no move to play
+    Failed(String),
+    /// The built-in search coming back once it's done
+    Idle(Search),
+}
+
+/// The built-in search, kept from one move to the next so that its transposition table
+/// carries over. It's away on the thinking thread while the computer thinks
+struct AiSearch(Option<Search>);
+impl Default for AiSearch {
+    fn default() -> Self {
+        AiSearch(Some(Search::new(SearchLimits::default())))
+    }
+}
+
+/// The search running in the background, and the position it was started from
+#[derive(Default)]
+struct AiThinking(Option<(Position, Mutex<Receiver<AiMessage>>)>);
+
+/// The increment the clock gives, which is what engines know how to use
+fn increment(clock: &Clock) -> Duration {
+    match clock.control().bonus {
+        TimeBonus::Increment(increment) => increment,
+        _ => Duration::default(),
+    }
+}
+
+/// How long to think with the time left on the clock: a share of it, plus most of
+/// the increment, while keeping a small margin
+fn think_time(clock: &Clock, color: PieceColor) -> Duration {
+    let remaining = clock.remaining(color);
+    let margin = Duration::from_millis(100);
+    (remaining / 30)
+        .saturating_add(increment(clock) / 4 * 3)
+        .min(remaining.saturating_sub(margin))
+}
+
+/// Starts searching on another thread when it's the computer's turn,
+/// so that the frame loop keeps running meanwhile. Book moves are played without searching
+fn start_thinking(
+    ai_player: Res<AiPlayer>,
+    game_status: Res<GameStatus>,
+    opening_book: Option<Res<OpeningBook>>,
+    external_engine: Option<Res<ExternalEngine>>,
+    game_clock: Res<GameClock>,
+    mut ai_search: ResMut<AiSearch>,
+    mut thinking: ResMut<AiThinking>,
+) {
+    if thinking.0.is_some()
+        || !matches!(game_status.status_type, StatusType::Move)
+        || !ai_player.plays(game_status.color)
+    {
+        return;
+    }
+
+    let game = game_status.game.clone();
+    let think_time = match &game_clock.0 {
+        Some(clock) => think_time(clock, game_status.color),
+        None => ai_player.think_time,
+    };
+    let (sender, receiver) = mpsc::channel();
+    let book_move =
+        opening_book.and_then(|book| book.0.choose_move(game.position(), book::random()));
+    if let Some(mv) = book_move {
+        let _ = sender.send(AiMessage::BestMove(mv));
+        thinking.0 = Some((game.position().clone(), Mutex::new(receiver)));
+        return;
+    }
+    match external_engine.filter(|_| ai_player.external) {
+        Some(external_engine) => {
+            let process = external_engine.process.clone();
+            let limits = match &game_clock.0 {
+                Some(clock) => GoLimits::Clock {
+                    white_time: clock.remaining(PieceColor::White),
+                    black_time: clock.remaining(PieceColor::Black),
+                    white_increment: increment(clock),
+                    black_increment: increment(clock),
+                },
+                None => GoLimits::MoveTime(think_time),
+            };
+            thread::spawn(move || {
+                let mut process = process.lock().unwrap();
+                let message = match process.as_mut() {
+                    Some(engine) => {
+                        let info_sender = sender.clone();
+                        let best_move = engine.go(&game, limits, |info| {
+                            let _ = info_sender.send(AiMessage::Info(info.clone()));
+                        });
+                        match best_move {
+                            Ok(Some(mv)) => AiMessage::BestMove(mv),
+                            Ok(None) => AiMessage::Failed("the engine has no move".to_string()),
+                            Err(err) => AiMessage::Failed(format!("the engine failed: {}", err)),
+                        }
+                    }
+                    None => AiMessage::Failed("the engine isn't running".to_string()),
+                };
+                // The game may be gone by the time the engine answers
+                let _ = sender.send(message);
+            });
+        }
+        None => {
+            let limits = SearchLimits {
+                depth: None,
+                time: Some(think_time),
+            };
+            // A search that was left behind when its game ended isn't coming back
+            let mut search = ai_search.0.take().unwrap_or_else(|| Search::new(limits));
+            search.set_limits(limits);
+            thread::spawn(move || {
+                let info_sender = sender.clone();
+                let best_move = search.run(&game, |info| {
+                    let _ = info_sender.send(AiMessage::Info(info.into()));
+                });
+                let _ = sender.send(AiMessage::Idle(search));
+                let _ = sender.send(match best_move {
+                    Some(mv) => AiMessage::BestMove(mv),
+                    None => AiMessage::Failed("the search found no move".to_string()),
+                });
+            });
+        }
+    }
+    thinking.0 = Some((game_status.game.position().clone(), Mutex::new(receiver)));
+}
+
+/// Keeps track of what the computer reports, and plays its move once it's done.
+/// A computer that can't come up with a legal move resigns, rather than being asked again
+fn finish_thinking(
+    mut game_status: ResMut<GameStatus>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+    mut analysis: ResMut<AiAnalysis>,
+    mut ai_search: ResMut<AiSearch>,
+    mut thinking: ResMut<AiThinking>,
+) {
+    let mut answer = None;
+    match &thinking.0 {
+        Some((_, receiver)) => {
+            for message in receiver.lock().unwrap().try_iter() {
+                match message {
+                    AiMessage::Info(info) => analysis.0 = Some(info),
+                    AiMessage::BestMove(mv) => answer = Some(Ok(mv)),
+                    AiMessage::Failed(reason) => answer = Some(Err(reason)),
+                    AiMessage::Idle(search) => ai_search.0 = Some(search),
+                }
+            }
+        }
+        None => return,
+    }
+    let answer = match answer {
+        Some(v) => v,
+        None => return,
+    };
+    let (position, _) = thinking.0.take().unwrap();
+
+    // Moves taken back while the computer was thinking make its answer useless, and so
+    // does the game ending with a resignation or a draw
+    if &position != game_status.game.position() || game_status.is_over() {
+        return;
+    }
+    // Moves from an external engine go through the same checks as the player's
+    let played = answer.and_then(|mv| game_status.play(mv).map_err(|err| err.to_string()));
+    match played {
+        Ok(()) => displayed_position.0 = game_status.game.position().clone(),
+        Err(reason) => {
+            eprintln!("The computer resigns, since {}", reason);
+            let color = game_status.color;
+            game_status.resign(color);
+        }
+    }
+}
+
+/// Forgets the search when the game ends. It runs out on its own, and its answer is dropped.
+/// What the built-in search found is of no use in the next game either
+fn stop_thinking(
+    mut thinking: ResMut<AiThinking>,
+    mut analysis: ResMut<AiAnalysis>,
+    mut ai_search: ResMut<AiSearch>,
+) {
+    thinking.0 = None;
+    analysis.0 = None;
+    if let Some(search) = ai_search.0.as_mut() {
+        search.clear_table();
+    }
+}
+
+pub struct AiPlugin;
+impl Plugin for AiPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<AiPlayer>()
+            .init_resource::<AiAnalysis>()
+            .init_resource::<AiSearch>()
+            .init_resource::<AiThinking>()
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(start_thinking.system().label("start_thinking"))
+                    .with_system(finish_thinking.system().before("start_thinking")),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame).with_system(stop_thinking.system()),
+            );
+    }
+}
@@ -0,0 +1,127 @@
+//! Plays chess through the Universal Chess Interface, using the rules and search of
+//! `chess_rules`, so that the engine can be used from chess GUIs and tournament managers.
+
+use std::{
+    io::{self, BufRead},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use chess_rules::{
+    parse_go, parse_position, Game, Search, SearchLimits, UciInfo, DEFAULT_TABLE_MB,
+};
+
+const MAX_HASH_MB: usize = 1024;
+
+/// The search running in the background, and the flag that stops it
+struct Searching {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<Search>,
+}
+impl Searching {
+    /// Stops the search, which then sends its best move, and gives it back
+    /// along with its hash table
+    fn stop(self) -> Search {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.join().expect("the search thread panicked")
+    }
+}
+
+/// Stops the search if it's running, and returns it. The search is always either
+/// idle or running, so that its hash table carries over from one move to the next
+fn idle_search<'a>(
+    idle: &'a mut Option<Search>,
+    searching: &mut Option<Searching>,
+) -> &'a mut Search {
+    if let Some(searching) = searching.take() {
+        *idle = Some(searching.stop());
+    }
+    idle.as_mut()
+        .expect("the search is neither idle nor running")
+}
+
+fn start_search(mut search: Search, game: Game, infinite: bool) -> Searching {
+    let stop = search.stop_handle();
+    let stopped = stop.clone();
+    let thread = thread::spawn(move || {
+        let best_move = search.run(&game, |info| println!("{}", UciInfo::from(info)));
+        // An infinite search only answers once it's told to stop
+        while infinite && !stopped.load(Ordering::Relaxed) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        match best_move {
+            Some(mv) => println!("bestmove {}", mv),
+            None => println!("bestmove 0000"),
+        }
+        search
+    });
+    Searching { stop, thread }
+}
+
+fn main() {
+    let mut game = Game::default();
+    let mut chess960 = false;
+    let mut idle = Some(Search::new(SearchLimits::default()));
+    let mut searching: Option<Searching> = None;
+
+    let stdin = io::stdin();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(v) => v,
+            Err(_) => break,
+        };
+        let words: Vec<_> = line.split_whitespace().collect();
+        let (command, args) = match words.split_first() {
+            Some((command, args)) => (*command, args),
+            None => continue,
+        };
+        match command {
+            "uci" => {
+                println!("id name Bevy Chess");
+                println!("id author guimcaballero");
+                println!(
+                    "option name Hash type spin default {} min 1 max {}",
+                    DEFAULT_TABLE_MB, MAX_HASH_MB
+                );
+                println!("option name UCI_Chess960 type check default false");
+                println!("uciok");
+            }
+            "isready" => println!("readyok"),
+            "ucinewgame" => {
+                idle_search(&mut idle, &mut searching).clear_table();
+                game = Game::default();
+            }
+            "setoption" => match args {
+                ["name", "Hash", "value", value] if value.parse::<usize>().is_ok() => {
+                    let megabytes = value.parse::<usize>().unwrap().clamp(1, MAX_HASH_MB);
+                    idle_search(&mut idle, &mut searching).resize_table(megabytes);
+                }
+                ["name", "UCI_Chess960", "value", value] if value.parse::<bool>().is_ok() => {
+                    chess960 = value.parse().unwrap();
+                }
+                _ => println!("info string unsupported option: {}", args.join(" ")),
+            },
+            "position" => match parse_position(args, chess960) {
+                Ok(v) => game = v,
+                Err(err) => println!("info string {}", err),
+            },
+            "go" => {
+                let (limits, infinite) = parse_go(args, game.position().side_to_move());
+                idle_search(&mut idle, &mut searching).set_limits(limits);
+                let search = idle.take().unwrap();
+                searching = Some(start_search(search, game.clone(), infinite));
+            }
+            "stop" => {
+                idle_search(&mut idle, &mut searching);
+            }
+            "quit" => break,
+            _ => println!("info string unknown command: {}", command),
+        }
+    }
+
+    idle_search(&mut idle, &mut searching);
+}
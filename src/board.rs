@@ -1,7 +1,19 @@
 use bevy::prelude::*;
 use bevy_mod_picking::{PickableBundle, PickingCamera};
+use chess_rules::{DrawReason, Game, GameResult, IllegalMoveError, Move, Outcome, WinReason};
 
-use crate::pieces::{is_check_mate_on, is_check_on, Piece, PieceColor, PieceType};
+use crate::{
+    ai::AiPlayer,
+    camera::viewing_side,
+    move_input::MoveInput,
+    move_list::Browsing,
+    pieces::{
+        pos_to_square, square_to_pos, tray_column, DisplayedPosition, Dragged, Piece, PieceColor,
+        PieceType,
+    },
+    replay::Replay,
+    state::{AppState, GameEntity},
+};
 
 pub struct Square {
     pub pos: IVec2,
@@ -36,15 +48,35 @@ fn create_board(
                     ..Default::default()
                 })
                 .insert_bundle(PickableBundle::default())
-                .insert(Square { pos: (i, j).into() });
+                .insert(Square { pos: (i, j).into() })
+                .insert(GameEntity);
         }
     }
+
+    // Spawn the trays for captured pieces, two columns wide, on each side of the board
+    let tray_mesh = meshes.add(Mesh::from(shape::Box::new(8., 0.05, 2.)));
+    for &color in [PieceColor::White, PieceColor::Black].iter() {
+        let first = tray_column(color) as f32;
+        let center = if first < 0. { first - 0.5 } else { first + 0.5 };
+        commands
+            .spawn_bundle(PbrBundle {
+                mesh: tray_mesh.clone(),
+                material: materials.tray_color.clone(),
+                transform: Transform::from_translation(Vec3::new(3.5, -0.03, center)),
+                ..Default::default()
+            })
+            .insert(GameEntity);
+    }
 }
 
 fn color_squares(
     selected_square: Res<SelectedSquare>,
+    keyboard_cursor: Res<KeyboardCursor>,
+    selected_piece: Res<SelectedPiece>,
+    game_status: Res<GameStatus>,
     materials: Res<SquareMaterials>,
     mut query: Query<(Entity, &Square, &mut Handle<StandardMaterial>)>,
+    pieces_query: Query<&Piece>,
     picking_camera_query: Query<&PickingCamera>,
 ) {
     // Get entity under the cursor, if there is one
@@ -55,12 +87,29 @@ fn color_squares(
         None => None,
     };
 
+    // Get the moves the selected piece can make
+    let position = game_status.game.position();
+    let legal_moves = match selected_piece.entity.map(|entity| pieces_query.get(entity)) {
+        Some(Ok(piece)) => position.legal_moves_from(pos_to_square(piece.pos)),
+        _ => Vec::new(),
+    };
+
     for (entity, square, mut material) in query.iter_mut() {
+        let legal_move = legal_moves
+            .iter()
+            .find(|mv| mv.to == pos_to_square(square.pos));
+
         // Change the material
-        *material = if Some(entity) == top_entity {
+        *material = if Some(entity) == top_entity || Some(square.pos) == keyboard_cursor.pos {
             materials.highlight_color.clone()
         } else if Some(entity) == selected_square.entity {
             materials.selected_color.clone()
+        } else if let Some(&mv) = legal_move {
+            if position.is_capture(mv) {
+                materials.capture_color.clone()
+            } else {
+                materials.move_color.clone()
+            }
         } else if square.is_white() {
             materials.white_color.clone()
         } else {
@@ -72,8 +121,11 @@ fn color_squares(
 struct SquareMaterials {
     highlight_color: Handle<StandardMaterial>,
     selected_color: Handle<StandardMaterial>,
+    move_color: Handle<StandardMaterial>,
+    capture_color: Handle<StandardMaterial>,
     black_color: Handle<StandardMaterial>,
     white_color: Handle<StandardMaterial>,
+    tray_color: Handle<StandardMaterial>,
 }
 
 impl FromWorld for SquareMaterials {
@@ -90,8 +142,11 @@ impl FromWorld for SquareMaterials {
         SquareMaterials {
             highlight_color: materials.add(make_material(0.8, 0.3, 0.3)),
             selected_color: materials.add(make_material(0.9, 0.1, 0.1)),
+            move_color: materials.add(make_material(0.3, 0.6, 0.3)),
+            capture_color: materials.add(make_material(0.9, 0.5, 0.1)),
             black_color: materials.add(make_material(0., 0.1, 0.1)),
             white_color: materials.add(make_material(1., 0.9, 0.9)),
+            tray_color: materials.add(make_material(0.35, 0.25, 0.2)),
         }
     }
 }
@@ -100,49 +155,208 @@ impl FromWorld for SquareMaterials {
 struct SelectedSquare {
     entity: Option<Entity>,
 }
+/// The square picked with the arrow keys, shown once one is pressed
+#[derive(Default)]
+struct KeyboardCursor {
+    pos: Option<IVec2>,
+}
 #[derive(Default)]
 struct SelectedPiece {
     entity: Option<Entity>,
 }
 
+/// The piece held with the mouse button, and the square it was picked up from
+#[derive(Default)]
+struct DraggedPiece {
+    entity: Option<Entity>,
+    origin: Option<Entity>,
+}
+
+/// Holds the move of the pawn that reached the last rank while its player picks a promotion
+#[derive(Default)]
+pub struct PendingPromotion(Option<Move>);
+impl PendingPromotion {
+    pub fn is_pending(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+/// Sent by the promotion picker with the piece type the player chose
+pub struct PromotionEvent(pub PieceType);
+
+/// Whether the game goes on, or who won and why it ended
+#[derive(Clone, Copy, PartialEq)]
 pub enum StatusType {
     Move,
-    Win,
+    Win(PieceColor, WinReason),
+    Draw(DrawReason),
 }
 
 pub struct GameStatus {
+    /// The side to move
     pub color: PieceColor,
     pub status_type: StatusType,
+    pub game: Game,
+    /// Moves taken back, the last one being the first to redo
+    undone: Vec<Move>,
 }
 impl Default for GameStatus {
     fn default() -> Self {
-        Self {
-            color: PieceColor::White,
-            status_type: StatusType::Move,
-        }
+        Self::new(Game::default())
     }
 }
 impl GameStatus {
-    fn update(&mut self, pieces: &[Piece]) {
-        match is_check_mate_on(pieces, self.color.other()) {
-            false => self.color = self.color.other(),
-            true => self.status_type = StatusType::Win,
+    pub fn new(game: Game) -> Self {
+        let mut game_status = Self {
+            color: game.position().side_to_move(),
+            status_type: StatusType::Move,
+            game,
+            undone: Vec::new(),
+        };
+        game_status.update();
+        game_status
+    }
+
+    /// Plays the move and passes the turn, or ends the game
+    pub fn play(&mut self, mv: Move) -> Result<(), IllegalMoveError> {
+        self.game.play(mv)?;
+        self.undone.clear();
+        self.update();
+        Ok(())
+    }
+
+    /// Takes back the last move, returning false if there is none
+    fn undo(&mut self) -> bool {
+        let mv = match self.game.moves().last() {
+            Some(&v) => v,
+            None => return false,
+        };
+        self.game.truncate(self.game.moves().len() - 1);
+        self.undone.push(mv);
+        self.update();
+        true
+    }
+
+    /// Plays again the last move taken back, returning false if there is none
+    fn redo(&mut self) -> bool {
+        let mv = match self.undone.pop() {
+            Some(v) => v,
+            None => return false,
+        };
+        // The move was legal when it was first played from this very position
+        self.game.play(mv).unwrap();
+        self.update();
+        true
+    }
+
+    /// The result to record for the game so far
+    pub fn result(&self) -> GameResult {
+        match self.status_type {
+            StatusType::Win(PieceColor::White, _) => GameResult::WhiteWins,
+            StatusType::Win(PieceColor::Black, _) => GameResult::BlackWins,
+            StatusType::Draw(_) => GameResult::Draw,
+            StatusType::Move => GameResult::Unknown,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.status_type != StatusType::Move
+    }
+
+    /// Ends the game because the side ran out of time
+    pub fn flag_fall(&mut self, color: PieceColor) {
+        let outcome = Outcome::timeout(self.game.position(), color);
+        self.set_outcome(Some(outcome));
+    }
+
+    /// Ends the game with the side giving up
+    pub fn resign(&mut self, color: PieceColor) {
+        self.set_outcome(Some(Outcome::Win(color.other(), WinReason::Resignation)));
+    }
+
+    /// Ends the game with both sides agreeing to a draw
+    pub fn agree_draw(&mut self) {
+        self.set_outcome(Some(Outcome::Draw(DrawReason::Agreement)));
+    }
+
+    /// Ends the game in a draw if the side to move can claim one, returning whether it did
+    pub fn claim_draw(&mut self) -> bool {
+        match self.game.claimable_draw() {
+            Some(reason) => {
+                self.set_outcome(Some(Outcome::Draw(reason)));
+                true
+            }
+            None => false,
         }
     }
+
+    fn update(&mut self) {
+        self.set_outcome(self.game.outcome());
+    }
+
+    fn set_outcome(&mut self, outcome: Option<Outcome>) {
+        self.color = self.game.position().side_to_move();
+        self.status_type = match outcome {
+            None => StatusType::Move,
+            Some(Outcome::Win(color, reason)) => StatusType::Win(color, reason),
+            Some(Outcome::Draw(reason)) => StatusType::Draw(reason),
+        };
+    }
+}
+
+/// Whether the player can't pick squares or play moves right now. A replayed game can
+/// only be stepped through, and the board is locked until the player picks a promotion,
+/// and while the engine thinks
+pub fn board_locked(
+    replaying: bool,
+    pending_promotion: &PendingPromotion,
+    ai_player: &AiPlayer,
+    game_status: &GameStatus,
+) -> bool {
+    replaying || pending_promotion.is_pending() || ai_player.plays(game_status.color)
 }
 
 fn select_square(
     mouse_button_inputs: Res<Input<MouseButton>>,
     mut selected_square: ResMut<SelectedSquare>,
     mut selected_piece: ResMut<SelectedPiece>,
+    pending_promotion: Res<PendingPromotion>,
+    replay: Option<Res<Replay>>,
+    ai_player: Res<AiPlayer>,
+    game_status: Res<GameStatus>,
+    mut browsing: ResMut<Browsing>,
     squares_query: Query<&Square>,
     picking_camera_query: Query<&PickingCamera>,
+    interaction_query: Query<&Interaction>,
 ) {
     // Only run if the left button is pressed
     if !mouse_button_inputs.just_pressed(MouseButton::Left) {
         return;
     }
 
+    // Clicks on buttons are for the buttons
+    if interaction_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        return;
+    }
+
+    // Past positions can't be played from, clicking the board goes back to the current one
+    if browsing.ply.is_some() {
+        browsing.ply = None;
+        return;
+    }
+
+    if board_locked(
+        replay.is_some(),
+        &pending_promotion,
+        &ai_player,
+        &game_status,
+    ) {
+        return;
+    }
+
     // Get the square under the cursor and set it as the selected
     if let Some(picking_camera) = picking_camera_query.iter().last() {
         if let Some((square_entity, _intersection)) = picking_camera.intersect_top() {
@@ -158,6 +372,72 @@ fn select_square(
     }
 }
 
+/// Moves the keyboard cursor with the arrow keys, as seen from the player's side, and
+/// selects its square on Enter like clicking it would
+fn keyboard_select_square(
+    keyboard_input: Res<Input<KeyCode>>,
+    move_input: Res<MoveInput>,
+    mut keyboard_cursor: ResMut<KeyboardCursor>,
+    mut selected_square: ResMut<SelectedSquare>,
+    pending_promotion: Res<PendingPromotion>,
+    replay: Option<Res<Replay>>,
+    ai_player: Res<AiPlayer>,
+    game_status: Res<GameStatus>,
+    mut browsing: ResMut<Browsing>,
+    squares_query: Query<(Entity, &Square)>,
+) {
+    // The keys are for the move box while it's focused, and for stepping through a replay
+    if move_input.focused || replay.is_some() {
+        return;
+    }
+
+    // x is the rank and y the file, so up goes towards the far rank
+    let mut step = IVec2::ZERO;
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        step.x += 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        step.x -= 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::Right) {
+        step.y += 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::Left) {
+        step.y -= 1;
+    }
+    let side = viewing_side(&ai_player, &game_status);
+    if side == PieceColor::Black {
+        step = -step;
+    }
+    if step != IVec2::ZERO {
+        // The cursor starts on the player's king
+        let start = keyboard_cursor.pos.unwrap_or_else(|| {
+            match game_status.game.position().king_square(side) {
+                Some(square) => square_to_pos(square),
+                None => IVec2::ZERO,
+            }
+        });
+        let pos = (start + step).max(IVec2::ZERO).min(IVec2::splat(7));
+        keyboard_cursor.pos = Some(pos);
+    }
+
+    let pos = match keyboard_cursor.pos {
+        Some(v) if keyboard_input.just_pressed(KeyCode::Return) => v,
+        _ => return,
+    };
+    // Past positions can't be played from, like with clicks
+    if browsing.ply.is_some() {
+        browsing.ply = None;
+        return;
+    }
+    if board_locked(false, &pending_promotion, &ai_player, &game_status) {
+        return;
+    }
+    if let Some((entity, _)) = squares_query.iter().find(|(_, square)| square.pos == pos) {
+        selected_square.entity = Some(entity);
+    }
+}
+
 fn select_piece(
     selected_square: Res<SelectedSquare>,
     mut selected_piece: ResMut<SelectedPiece>,
@@ -179,6 +459,11 @@ fn select_piece(
         _ => return,
     };
 
+    // Nobody can move once the game is over
+    if !matches!(game_status.status_type, StatusType::Move) {
+        return;
+    }
+
     if selected_piece.entity.is_none() {
         // Select the piece in the currently selected square
         for (piece_entity, piece) in pieces_query.iter() {
@@ -192,12 +477,13 @@ fn select_piece(
 }
 
 fn move_piece(
-    mut commands: Commands,
     selected_square: Res<SelectedSquare>,
     selected_piece: Res<SelectedPiece>,
-    mut turn: ResMut<GameStatus>,
+    mut game_status: ResMut<GameStatus>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut displayed_position: ResMut<DisplayedPosition>,
     squares_query: Query<&Square>,
-    mut pieces_query: Query<(Entity, &mut Piece)>,
+    pieces_query: Query<&Piece>,
     mut reset_selected_event: EventWriter<ResetSelectedEvent>,
 ) {
     if !selected_square.is_changed() {
@@ -216,56 +502,227 @@ fn move_piece(
         Some(v) => v,
         _ => return,
     };
-    let pieces_before_move: Vec<_> = pieces_query.iter_mut().map(|(_, piece)| *piece).collect();
-
-    let (_, mut piece) = match pieces_query.get_mut(selected_piece_entity) {
-        Ok(v) => v,
+    let piece = match pieces_query.get(selected_piece_entity) {
+        Ok(v) => *v,
         _ => return,
     };
 
     reset_selected_event.send(ResetSelectedEvent);
 
-    let piece_color = piece.color;
-    if !piece.is_move_valid(square.pos, &pieces_before_move) {
+    let mv = Move::new(pos_to_square(piece.pos), pos_to_square(square.pos));
+    try_move(
+        mv,
+        &mut game_status,
+        &mut pending_promotion,
+        &mut displayed_position,
+    );
+}
+
+/// Plays the move if it's legal, returning whether it was. A pawn reaching the last
+/// rank without a promotion piece waits there for the player to pick one
+pub fn try_move(
+    mv: Move,
+    game_status: &mut GameStatus,
+    pending_promotion: &mut PendingPromotion,
+    displayed_position: &mut DisplayedPosition,
+) -> bool {
+    let position = game_status.game.position();
+    // Castling in Chess960 is the king taking its own rook, but putting the king
+    // where it lands does too
+    let castling = position
+        .legal_moves_from(mv.from)
+        .into_iter()
+        .find(|&castling| {
+            position.castling_rook(castling).is_some() && position.landing_square(castling) == mv.to
+        });
+    let mv = match castling {
+        Some(castling) if !position.is_legal(mv) => castling,
+        _ => mv,
+    };
+    if mv.promotion.is_none() && position.is_promotion(mv.from, mv.to) {
+        // Any promotion piece will do to know if the move is legal
+        let promotion = Move {
+            promotion: Some(PieceType::Queen),
+            ..mv
+        };
+        if !position.is_legal(promotion) {
+            return false;
+        }
+        // The turn only passes once the player has picked the new piece,
+        // meanwhile the pawn waits on the last rank
+        let mut waiting = position.clone();
+        waiting.make_move(mv);
+        displayed_position.0 = waiting;
+        pending_promotion.0 = Some(mv);
+        true
+    } else if game_status.play(mv).is_ok() {
+        displayed_position.0 = game_status.game.position().clone();
+        true
+    } else {
+        false
+    }
+}
+
+/// How high a dragged piece is held above the board
+const DRAG_HEIGHT: f32 = 0.5;
+
+/// Picks up the piece selected by pressing the mouse button, and makes it follow the
+/// cursor over the board while the button is held
+fn drag_piece(
+    mut commands: Commands,
+    mouse_button_inputs: Res<Input<MouseButton>>,
+    selected_square: Res<SelectedSquare>,
+    selected_piece: Res<SelectedPiece>,
+    mut dragged_piece: ResMut<DraggedPiece>,
+    mut pieces_query: Query<&mut Transform, With<Piece>>,
+    picking_camera_query: Query<&PickingCamera>,
+) {
+    if mouse_button_inputs.just_pressed(MouseButton::Left) && selected_piece.is_changed() {
+        if let Some(entity) = selected_piece.entity {
+            commands.entity(entity).insert(Dragged);
+            dragged_piece.entity = Some(entity);
+            dragged_piece.origin = selected_square.entity;
+        }
+    }
+
+    let entity = match dragged_piece.entity {
+        Some(v) if mouse_button_inputs.pressed(MouseButton::Left) => v,
+        _ => return,
+    };
+    let ray = match picking_camera_query.iter().last() {
+        Some(picking_camera) => match picking_camera.ray() {
+            Some(v) => v,
+            None => return,
+        },
+        None => return,
+    };
+    // Where the ray under the cursor meets the plane of the board
+    let (origin, direction) = (ray.origin(), ray.direction());
+    if direction.y >= 0. {
         return;
     }
+    let point = origin - direction * (origin.y / direction.y);
+    if let Ok(mut transform) = pieces_query.get_mut(entity) {
+        transform.translation = Vec3::new(point.x, DRAG_HEIGHT, point.z);
+    }
+}
 
-    let pieces_after_move: Vec<_> = piece.get_pieces_after_move(square.pos, &pieces_before_move);
-    if is_check_on(&pieces_after_move, piece_color) {
+/// Drops the dragged piece when the mouse button is released. Dropping it on another
+/// square selects that square, which plays the move if it's legal. Either way the
+/// piece then slides to its square, so illegal drops go back where they came from
+fn drop_piece(
+    mut commands: Commands,
+    mouse_button_inputs: Res<Input<MouseButton>>,
+    mut selected_square: ResMut<SelectedSquare>,
+    mut dragged_piece: ResMut<DraggedPiece>,
+    squares_query: Query<&Square>,
+    pieces_query: Query<&Piece>,
+    picking_camera_query: Query<&PickingCamera>,
+) {
+    if !mouse_button_inputs.just_released(MouseButton::Left) {
         return;
     }
-    let should_castle =
-        piece.piece_type == PieceType::King && (square.pos.y - piece.pos.y).abs() == 2;
+    let entity = match dragged_piece.entity.take() {
+        Some(v) => v,
+        None => return,
+    };
+    let origin = dragged_piece.origin.take();
+    // The piece may be gone, like when a new game starts while it's held
+    if pieces_query.get(entity).is_ok() {
+        commands.entity(entity).remove::<Dragged>();
+    }
 
-    // Move piece
-    piece.pos = square.pos;
-    piece.has_moved = true;
-    turn.update(&pieces_after_move);
+    let target = picking_camera_query
+        .iter()
+        .last()
+        .and_then(|camera| camera.intersect_top())
+        .map(|(entity, _intersection)| entity)
+        .filter(|&entity| squares_query.get(entity).is_ok());
+    // Releasing on the square the piece was picked from is a click, and leaves it selected
+    if target.is_some() && target != origin {
+        selected_square.entity = target;
+    }
+}
 
-    // Check if a piece of the opposite color exists in this square and despawn it
-    if let Some((entity, _)) = pieces_query
-        .iter_mut()
-        .find(|(_, other)| other.pos == square.pos && other.color != piece_color)
-    {
-        commands.entity(entity).despawn_recursive();
+fn promote_piece(
+    mut event_reader: EventReader<PromotionEvent>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut game_status: ResMut<GameStatus>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+) {
+    let event = match event_reader.iter().last() {
+        Some(v) => v,
+        _ => return,
     };
-    // Castle
-    if should_castle {
-        let (rook_entity, _) = pieces_query
-            .iter_mut()
-            .find(|(_, candidate)| {
-                candidate.piece_type == PieceType::Rook
-                    && candidate.pos.y == (if square.pos.y == 6 { 7 } else { 0 })
-                    && candidate.color == piece_color
-            })
-            .unwrap();
-        let (_, mut rook) = pieces_query.get_mut(rook_entity).unwrap();
-        rook.pos.y = if square.pos.y == 6 { 5 } else { 3 };
-        rook.has_moved = true;
+    let mv = match pending_promotion.0.take() {
+        Some(v) => v,
+        _ => return,
+    };
+
+    // Now that the promoted piece is known, check and checkmate can be evaluated
+    let mv = Move {
+        promotion: Some(event.0),
+        ..mv
+    };
+    if game_status.play(mv).is_ok() {
+        displayed_position.0 = game_status.game.position().clone();
     }
 }
 
-struct ResetSelectedEvent;
+/// Takes back the last move on Ctrl+Z, and plays it again on Ctrl+Y
+fn undo_redo(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut game_status: ResMut<GameStatus>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+    replay: Option<Res<Replay>>,
+    ai_player: Res<AiPlayer>,
+    mut reset_selected_event: EventWriter<ResetSelectedEvent>,
+) {
+    // A replayed game has its own controls
+    if replay.is_some() {
+        return;
+    }
+    let ctrl =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+    if !ctrl {
+        return;
+    }
+
+    // Against the engine, its replies are taken back and played again along with the moves
+    let engine_to_move =
+        |game_status: &GameStatus| ai_player.plays(game_status.game.position().side_to_move());
+    let changed = if keyboard_input.just_pressed(KeyCode::Z) {
+        // A pawn waiting for its promotion goes back first
+        let changed = pending_promotion.0.take().is_some() || game_status.undo();
+        while engine_to_move(&game_status) && game_status.undo() {}
+        changed
+    } else if keyboard_input.just_pressed(KeyCode::Y) {
+        let changed = !pending_promotion.is_pending() && game_status.redo();
+        while changed && engine_to_move(&game_status) && game_status.redo() {}
+        changed
+    } else {
+        false
+    };
+    if changed {
+        reset_selected_event.send(ResetSelectedEvent);
+        displayed_position.0 = game_status.game.position().clone();
+    }
+}
+
+/// Prints the current position as FEN when F is pressed
+fn print_fen(
+    keyboard_input: Res<Input<KeyCode>>,
+    move_input: Res<MoveInput>,
+    game_status: Res<GameStatus>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F) && !move_input.focused {
+        println!("FEN: {}", game_status.game.position().to_fen());
+    }
+}
+
+/// Clears the selected square and piece, like after a move
+pub struct ResetSelectedEvent;
 
 fn reset_selected(
     mut event_reader: EventReader<ResetSelectedEvent>,
@@ -285,23 +742,38 @@ impl Plugin for BoardPlugin {
             .init_resource::<SelectedPiece>()
             .init_resource::<SquareMaterials>()
             .init_resource::<GameStatus>()
+            .init_resource::<PendingPromotion>()
+            .init_resource::<DraggedPiece>()
+            .init_resource::<KeyboardCursor>()
             .add_event::<ResetSelectedEvent>()
-            .add_startup_system(create_board.system())
-            .add_system(color_squares.system())
-            .add_system(select_square.system().label("select_square"))
-            .add_system(
-                // move_piece needs to run before select_piece
-                move_piece
-                    .system()
-                    .after("select_square")
-                    .before("select_piece"),
-            )
-            .add_system(
-                select_piece
-                    .system()
-                    .after("select_square")
-                    .label("select_piece"),
+            .add_event::<PromotionEvent>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame).with_system(create_board.system()),
             )
-            .add_system(reset_selected.system().after("select_square"));
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(color_squares.system())
+                    .with_system(select_square.system().label("select_square"))
+                    .with_system(
+                        // move_piece needs to run before select_piece
+                        move_piece
+                            .system()
+                            .after("select_square")
+                            .before("select_piece"),
+                    )
+                    .with_system(
+                        select_piece
+                            .system()
+                            .after("select_square")
+                            .label("select_piece"),
+                    )
+                    .with_system(drag_piece.system().after("select_piece"))
+                    .with_system(drop_piece.system().before("select_square"))
+                    .with_system(keyboard_select_square.system().before("select_square"))
+                    .with_system(reset_selected.system().after("select_square"))
+                    .with_system(promote_piece.system())
+                    .with_system(undo_redo.system())
+                    .with_system(print_fen.system()),
+            );
     }
 }
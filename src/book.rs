@@ -0,0 +1,74 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+use bevy::prelude::*;
+use chess_rules::{Book, Move};
+
+use crate::{board::GameStatus, move_input::MoveInput, state::AppState};
+
+/// A Polyglot opening book given with `--book`, which the computer plays from while it can
+pub struct OpeningBook(pub Book);
+
+/// The book's moves in the current position with their weights, none once out of book
+#[derive(Default)]
+pub struct BookMoves(pub Vec<(Move, u16)>);
+
+/// A book move picked for the player with H, shown until the game moves on
+#[derive(Default)]
+pub struct BookHint(pub Option<Move>);
+
+/// A number spread evenly over all the values of a u64, different every time
+pub fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// Looks up the current position in the book whenever the game changes, which also
+/// takes away the hint
+fn update_book_moves(
+    opening_book: Option<Res<OpeningBook>>,
+    game_status: Res<GameStatus>,
+    mut book_moves: ResMut<BookMoves>,
+    mut hint: ResMut<BookHint>,
+) {
+    if !game_status.is_changed() {
+        return;
+    }
+    let position = game_status.game.position();
+    let moves = match opening_book {
+        Some(book) if !game_status.is_over() => book.0.moves(position),
+        _ => Vec::new(),
+    };
+    book_moves.0 = moves;
+    hint.0 = None;
+}
+
+/// Picks one of the book's moves for the player, as the computer would
+fn book_hint_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    move_input: Res<MoveInput>,
+    opening_book: Option<Res<OpeningBook>>,
+    game_status: Res<GameStatus>,
+    mut hint: ResMut<BookHint>,
+) {
+    if move_input.focused || !keyboard_input.just_pressed(KeyCode::H) {
+        return;
+    }
+    if let Some(book) = opening_book {
+        hint.0 = book.0.choose_move(game_status.game.position(), random());
+    }
+}
+
+pub struct BookPlugin;
+impl Plugin for BookPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<BookMoves>()
+            .init_resource::<BookHint>()
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(update_book_moves.system().label("book_moves"))
+                    .with_system(book_hint_key.system().after("book_moves")),
+            );
+    }
+}
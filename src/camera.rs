@@ -3,7 +3,7 @@ use std::f32::consts::PI;
 use bevy::{prelude::*, render::camera::PerspectiveProjection};
 use bevy_mod_picking::PickingCameraBundle;
 
-use crate::{board::GameStatus, pieces::PieceColor};
+use crate::{ai::AiPlayer, board::GameStatus, pieces::PieceColor};
 
 const ACCELERATION: f32 = PI * 2.;
 const INITIAL_SPEED: f32 = 0.5;
@@ -55,13 +55,23 @@ fn setup(mut commands: Commands, camera_yaw: Res<CameraPosition>) {
         });
 }
 
+/// The side the board is seen from. Against the engine the camera stays on the
+/// player's side, otherwise it turns to the side to move
+pub fn viewing_side(ai_player: &AiPlayer, game_status: &GameStatus) -> PieceColor {
+    match ai_player.color {
+        Some(ai_color) => ai_color.other(),
+        None => game_status.color,
+    }
+}
+
 fn reposition_camera(
     time: Res<Time>,
     game_status: Res<GameStatus>,
+    ai_player: Res<AiPlayer>,
     mut camera_position: ResMut<CameraPosition>,
     mut camera_query: Query<&mut Transform, With<PerspectiveProjection>>,
 ) {
-    let target_yaw = match game_status.color {
+    let target_yaw = match viewing_side(&ai_player, &game_status) {
         PieceColor::White => -PI / 2.,
         PieceColor::Black => PI / 2.,
     };
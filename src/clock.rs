@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use chess_rules::Clock;
+
+use crate::{
+    board::{GameStatus, StatusType},
+    state::AppState,
+};
+
+/// The chess clock, if the game is played with a time control
+#[derive(Default)]
+pub struct GameClock(pub Option<Clock>);
+
+/// Runs the clock of the side to move, hands it over when a move is played,
+/// and ends the game when a flag falls
+fn run_clock(
+    time: Res<Time>,
+    mut game_status: ResMut<GameStatus>,
+    mut game_clock: ResMut<GameClock>,
+    mut moves_seen: Local<usize>,
+) {
+    let clock = match game_clock.0.as_mut() {
+        Some(v) => v,
+        None => return,
+    };
+
+    let moves = game_status.game.moves().len();
+    if moves == *moves_seen + 1 {
+        clock.press();
+    } else if moves != *moves_seen {
+        // Moves were taken back or played again, which doesn't earn any time
+        clock.switch_to(game_status.game.position().side_to_move());
+    }
+    *moves_seen = moves;
+
+    if !matches!(game_status.status_type, StatusType::Move) {
+        return;
+    }
+    clock.tick(time.delta());
+    if let Some(color) = clock.flagged() {
+        game_status.flag_fall(color);
+    }
+}
+
+pub struct ClockPlugin;
+impl Plugin for ClockPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<GameClock>().add_system_set(
+            // The clocks stop while the game is paused
+            SystemSet::on_update(AppState::InGame).with_system(run_clock.system()),
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use bevy::prelude::*;
+use chess_rules::UciEngine;
+
+/// An engine on disk that speaks UCI, playing instead of the built-in one
+pub struct ExternalEngine {
+    pub path: PathBuf,
+    // Started in the background, so that a slow engine doesn't hold up the first frame.
+    // Whoever needs it locks it, and gets it once it's ready
+    pub process: Arc<Mutex<Option<UciEngine>>>,
+}
+impl ExternalEngine {
+    pub fn new(path: PathBuf) -> Self {
+        ExternalEngine {
+            path,
+            process: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+fn start_engine(external_engine: Option<Res<ExternalEngine>>) {
+    let external_engine = match external_engine {
+        Some(v) => v,
+        None => return,
+    };
+    let path = external_engine.path.clone();
+    let process = external_engine.process.clone();
+    thread::spawn(move || {
+        let mut process = process.lock().unwrap();
+        match UciEngine::spawn(&path) {
+            Ok(engine) => {
+                println!(
+                    "Playing against {}",
+                    engine.name().unwrap_or("the external engine")
+                );
+                *process = Some(engine);
+            }
+            Err(err) => eprintln!("Could not start {}: {}", path.display(), err),
+        }
+    });
+}
+
+pub struct EnginePlugin;
+impl Plugin for EnginePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_startup_system(start_engine.system());
+    }
+}
@@ -1,17 +1,239 @@
+use std::{env, fs, process, time::Instant};
+
+use actions::ActionsPlugin;
+use ai::AiPlugin;
 use bevy::prelude::*;
 use bevy_mod_picking::{PickingCamera, PickingPlugin};
 use board::BoardPlugin;
+use book::{BookPlugin, OpeningBook};
 use camera::CameraPlugin;
+use chess_rules::{divide, Book, PgnGame, PieceColor, Position, TimeControl};
+use clock::ClockPlugin;
+use engine::{EnginePlugin, ExternalEngine};
+use menu::{GameMode, GameSettings, MenuPlugin, Variant};
+use move_input::MoveInputPlugin;
+use move_list::MoveListPlugin;
+use move_log::MoveLogPlugin;
 use pieces::PiecesPlugin;
+use replay::ReplayPlugin;
+use state::StatePlugin;
 use ui::UIPlugin;
 
+mod actions;
+mod ai;
 mod board;
+mod book;
 mod camera;
+mod clock;
+mod engine;
+mod menu;
+mod move_input;
+mod move_list;
+mod move_log;
 mod pieces;
+mod replay;
+mod state;
 mod ui;
 
+/// Reads the starting position from `--fen <FEN>` or `--fen-file <PATH>`,
+/// defaulting to the standard one
+fn starting_position() -> Position {
+    let args: Vec<_> = env::args().collect();
+    let fen = match args
+        .iter()
+        .position(|arg| arg == "--fen" || arg == "--fen-file")
+    {
+        Some(i) if i + 1 < args.len() => {
+            if args[i] == "--fen" {
+                args[i + 1].clone()
+            } else {
+                fs::read_to_string(&args[i + 1]).unwrap_or_else(|err| {
+                    eprintln!("Could not read {}: {}", args[i + 1], err);
+                    process::exit(1);
+                })
+            }
+        }
+        Some(_) => {
+            eprintln!("Usage: bevy_chess [--fen <FEN> | --fen-file <PATH> | --pgn <PATH>]");
+            process::exit(1);
+        }
+        None => return Position::default(),
+    };
+    Position::from_fen(fen.trim()).unwrap_or_else(|err| {
+        eprintln!("Invalid FEN: {}", err);
+        process::exit(1);
+    })
+}
+
+/// Reads `--chess960 [INDEX]`, to play Chess960 from the given start position,
+/// or a random one for each game
+fn chess960() -> Option<Variant> {
+    let args: Vec<_> = env::args().collect();
+    let i = args.iter().position(|arg| arg == "--chess960")?;
+    let index = match args.get(i + 1) {
+        Some(arg) if !arg.starts_with("--") => match arg.parse() {
+            Ok(index) if index < 960 => Some(index),
+            _ => {
+                eprintln!("Usage: bevy_chess --chess960 [0-959]");
+                process::exit(1);
+            }
+        },
+        _ => None,
+    };
+    Some(Variant::Chess960(index))
+}
+
+/// Reads the side played by the computer from `--ai <white|black>`
+fn ai_color() -> Option<PieceColor> {
+    let args: Vec<_> = env::args().collect();
+    let i = args.iter().position(|arg| arg == "--ai")?;
+    match args.get(i + 1).map(String::as_str) {
+        Some("white") => Some(PieceColor::White),
+        Some("black") => Some(PieceColor::Black),
+        _ => {
+            eprintln!("Usage: bevy_chess --ai <white|black>");
+            process::exit(1);
+        }
+    }
+}
+
+/// Reads the time control from `--time <CONTROL>`, like `300+5`
+fn time_control() -> Option<TimeControl> {
+    let args: Vec<_> = env::args().collect();
+    let i = args.iter().position(|arg| arg == "--time")?;
+    let control = args.get(i + 1).unwrap_or_else(|| {
+        eprintln!("Usage: bevy_chess --time <CONTROL>");
+        process::exit(1);
+    });
+    Some(control.parse().unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        process::exit(1);
+    }))
+}
+
+/// Reads the path of a UCI engine to play against from `--engine <PATH>`
+fn external_engine() -> Option<ExternalEngine> {
+    let args: Vec<_> = env::args().collect();
+    let i = args.iter().position(|arg| arg == "--engine")?;
+    match args.get(i + 1) {
+        Some(path) => Some(ExternalEngine::new(path.into())),
+        None => {
+            eprintln!("Usage: bevy_chess --engine <PATH> [--ai <white|black>]");
+            process::exit(1);
+        }
+    }
+}
+
+/// Loads the opening book the computer plays from with `--book <PATH>`, if given
+fn opening_book() -> Option<OpeningBook> {
+    let args: Vec<_> = env::args().collect();
+    let i = args.iter().position(|arg| arg == "--book")?;
+    let path = args.get(i + 1).unwrap_or_else(|| {
+        eprintln!("Usage: bevy_chess --book <PATH>");
+        process::exit(1);
+    });
+    let book = Book::open(path).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {}", path, err);
+        process::exit(1);
+    });
+    Some(OpeningBook(book))
+}
+
+/// Loads the games to replay from `--pgn <PATH>`, if given
+fn pgn_games() -> Option<Vec<PgnGame>> {
+    let args: Vec<_> = env::args().collect();
+    let i = args.iter().position(|arg| arg == "--pgn")?;
+    let path = args.get(i + 1).unwrap_or_else(|| {
+        eprintln!("Usage: bevy_chess [--fen <FEN> | --fen-file <PATH> | --pgn <PATH>]");
+        process::exit(1);
+    });
+    let pgn = fs::read_to_string(path).unwrap_or_else(|err| {
+        eprintln!("Could not read {}: {}", path, err);
+        process::exit(1);
+    });
+    let games = PgnGame::parse_all(&pgn).unwrap_or_else(|err| {
+        eprintln!("Invalid PGN: {}", err);
+        process::exit(1);
+    });
+    if games.is_empty() {
+        eprintln!("No games found in {}", path);
+        process::exit(1);
+    }
+    Some(games)
+}
+
+/// The menu starts out with the choices given on the command line
+fn game_settings(external_engine: &Option<ExternalEngine>) -> GameSettings {
+    let pgn_games = pgn_games();
+    let mut settings = GameSettings::new(
+        starting_position(),
+        time_control(),
+        pgn_games.clone().unwrap_or_default(),
+        external_engine.is_some(),
+    );
+    let ai_color = ai_color();
+    settings.mode = if pgn_games.is_some() {
+        GameMode::Replay
+    } else if external_engine.is_some() {
+        // An external engine plays Black unless told otherwise
+        GameMode::VsEngine
+    } else if ai_color.is_some() {
+        GameMode::VsAi
+    } else {
+        GameMode::HotSeat
+    };
+    settings.side = ai_color.map_or(PieceColor::White, |color| color.other());
+    if let Some(variant) = chess960() {
+        settings.pick_variant(variant);
+    }
+    settings
+}
+
+/// `bevy_chess perft <DEPTH>` counts the positions after every sequence of moves, split
+/// by the first move, from the starting position or the one given with `--fen`.
+/// Returns false if the subcommand isn't used
+fn run_perft() -> bool {
+    let args: Vec<_> = env::args().collect();
+    if args.get(1).map(String::as_str) != Some("perft") {
+        return false;
+    }
+    let depth = match args.get(2).map(|depth| depth.parse()) {
+        Some(Ok(v)) => v,
+        _ => {
+            eprintln!("Usage: bevy_chess perft <DEPTH> [--fen <FEN> | --fen-file <PATH>]");
+            process::exit(1);
+        }
+    };
+    let position = starting_position();
+    let start = Instant::now();
+    let mut moves = divide(&position, depth);
+    moves.sort_by_key(|(mv, _)| mv.to_string());
+    for (mv, count) in moves.iter() {
+        println!("{}: {}", mv, count);
+    }
+    let total: u64 = moves.iter().map(|(_, count)| count).sum();
+    println!();
+    println!("Moves: {}", moves.len());
+    println!("Nodes: {}", total);
+    println!("Time: {:.3}s", start.elapsed().as_secs_f64());
+    true
+}
+
 fn main() {
-    App::build()
+    if run_perft() {
+        return;
+    }
+
+    let mut app = App::build();
+    let external_engine = external_engine();
+    app.insert_resource(game_settings(&external_engine));
+    if let Some(external_engine) = external_engine {
+        app.insert_resource(external_engine);
+    }
+    if let Some(opening_book) = opening_book() {
+        app.insert_resource(opening_book);
+    }
+    app
         // Set antialiasing to use 4 samples
         .insert_resource(Msaa { samples: 4 })
         // Set WindowDescriptor Resource to change title and size
@@ -24,9 +246,20 @@ fn main() {
         .add_plugins(DefaultPlugins)
         .init_resource::<PickingCamera>()
         .add_plugin(PickingPlugin)
+        .add_plugin(StatePlugin)
+        .add_plugin(MenuPlugin)
         .add_plugin(BoardPlugin)
         .add_plugin(PiecesPlugin)
         .add_plugin(CameraPlugin)
         .add_plugin(UIPlugin)
+        .add_plugin(MoveLogPlugin)
+        .add_plugin(MoveListPlugin)
+        .add_plugin(MoveInputPlugin)
+        .add_plugin(ActionsPlugin)
+        .add_plugin(ReplayPlugin)
+        .add_plugin(AiPlugin)
+        .add_plugin(BookPlugin)
+        .add_plugin(EnginePlugin)
+        .add_plugin(ClockPlugin)
         .run();
 }
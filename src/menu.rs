@@ -0,0 +1,414 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use chess_rules::{Clock, Game, PgnGame, PieceColor, Position, TimeControl};
+
+use crate::{
+    ai::AiPlayer,
+    board::{GameStatus, PendingPromotion},
+    clock::GameClock,
+    pieces::DisplayedPosition,
+    replay::Replay,
+    state::{despawn_all, spawn_button, spawn_text, AppState, ScreenMaterials},
+};
+
+/// Who plays against whom
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GameMode {
+    /// Two players taking turns at the same computer
+    HotSeat,
+    /// Against the built-in engine
+    VsAi,
+    /// Against the UCI engine given with `--engine`
+    VsEngine,
+    /// Finding the mate in a position, against the built-in engine
+    Puzzle,
+    /// Stepping through the games of the PGN file given with `--pgn`
+    Replay,
+}
+
+/// How the pieces are set up, in games that aren't puzzles or replays
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Variant {
+    /// From the standard position, or the one given with `--fen`
+    Standard,
+    /// From a Chess960 position, picked at random for each game unless its number is given
+    Chess960(Option<u16>),
+}
+
+/// Positions where the side to move can force mate
+const PUZZLES: [(&str, &str); 5] = [
+    ("6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1", "White mates in 1"),
+    (
+        "r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4",
+        "White mates in 1",
+    ),
+    ("6rk/6pp/8/6N1/8/8/8/6QK w - - 0 1", "White mates in 1"),
+    (
+        "r2qkb1r/pp2nppp/3p4/2pNN1B1/2BnP3/3P4/PPP2PPP/R2bK2R w KQkq - 1 10",
+        "White mates in 2",
+    ),
+    (
+        "r1b1kb1r/pppp1ppp/5q2/4n3/3KP3/2N3PN/PPP4P/R1BQ1B1R b kq - 0 1",
+        "Black mates in 3",
+    ),
+];
+
+/// The choices made in the main menu, which the next game starts from. The command
+/// line arguments pick the defaults
+pub struct GameSettings {
+    pub mode: GameMode,
+    /// The side the player takes against the computer
+    pub side: PieceColor,
+    /// Where games start, unless they're puzzles. Set with `--fen`
+    pub start_position: Position,
+    /// The time controls to pick from, `None` being no clock
+    pub time_controls: Vec<Option<TimeControl>>,
+    /// Index of the one picked
+    pub time_control: usize,
+    /// The setups to pick from
+    pub variants: Vec<Variant>,
+    /// Index of the one picked
+    pub variant: usize,
+    /// Index of the puzzle to solve
+    pub puzzle: usize,
+    /// The games read with `--pgn`, if any
+    pub pgn_games: Vec<PgnGame>,
+    /// Whether an engine was given with `--engine`
+    pub has_engine: bool,
+}
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self::new(Position::default(), None, Vec::new(), false)
+    }
+}
+impl GameSettings {
+    /// Settings for a hot-seat game, offering the given time control besides the usual ones
+    pub fn new(
+        start_position: Position,
+        time_control: Option<TimeControl>,
+        pgn_games: Vec<PgnGame>,
+        has_engine: bool,
+    ) -> Self {
+        let mut time_controls: Vec<_> =
+            [None, Some("60"), Some("180+2"), Some("300"), Some("600+5")]
+                .iter()
+                .map(|control| control.map(|control| control.parse().unwrap()))
+                .collect();
+        if !time_controls.contains(&time_control) {
+            time_controls.push(time_control.clone());
+        }
+        GameSettings {
+            mode: GameMode::HotSeat,
+            side: PieceColor::White,
+            start_position,
+            time_control: time_controls
+                .iter()
+                .position(|control| *control == time_control)
+                .unwrap(),
+            time_controls,
+            variants: vec![Variant::Standard, Variant::Chess960(None)],
+            variant: 0,
+            puzzle: 0,
+            pgn_games,
+            has_engine,
+        }
+    }
+
+    /// Picks the variant, offering it besides the usual ones
+    pub fn pick_variant(&mut self, variant: Variant) {
+        if !self.variants.contains(&variant) {
+            self.variants.push(variant);
+        }
+        self.variant = self.variants.iter().position(|&v| v == variant).unwrap();
+    }
+
+    /// The modes that can be played, which needs an engine or games to replay for some
+    fn modes(&self) -> Vec<GameMode> {
+        [
+            GameMode::HotSeat,
+            GameMode::VsAi,
+            GameMode::VsEngine,
+            GameMode::Puzzle,
+            GameMode::Replay,
+        ]
+        .iter()
+        .copied()
+        .filter(|&mode| match mode {
+            GameMode::VsEngine => self.has_engine,
+            GameMode::Replay => !self.pgn_games.is_empty(),
+            _ => true,
+        })
+        .collect()
+    }
+
+    /// The position the game starts from
+    fn position(&self) -> Position {
+        match self.mode {
+            GameMode::Puzzle => Position::from_fen(PUZZLES[self.puzzle].0).unwrap(),
+            _ => match self.variants[self.variant] {
+                Variant::Standard => self.start_position.clone(),
+                Variant::Chess960(index) => {
+                    Position::chess960(index.unwrap_or_else(random_chess960)).unwrap()
+                }
+            },
+        }
+    }
+
+    /// The side played by the computer, if any. In puzzles the player is the side to move
+    fn ai_color(&self) -> Option<PieceColor> {
+        match self.mode {
+            GameMode::VsAi | GameMode::VsEngine => Some(self.side.other()),
+            GameMode::Puzzle => Some(self.position().side_to_move().other()),
+            GameMode::HotSeat | GameMode::Replay => None,
+        }
+    }
+
+    /// The time control of the game, if it's played with a clock
+    fn time_control(&self) -> Option<&TimeControl> {
+        match self.mode {
+            GameMode::Replay => None,
+            _ => self.time_controls[self.time_control].as_ref(),
+        }
+    }
+}
+
+/// A Chess960 start position number, different from game to game
+fn random_chess960() -> u16 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.subsec_nanos())
+        .unwrap_or(0);
+    (nanos % 960) as u16
+}
+
+// Component to mark the root node of the main menu
+struct MainMenuScreen;
+
+/// What the buttons of the main menu do. All but Start cycle through the choices
+#[derive(Clone, Copy, PartialEq)]
+enum MenuButton {
+    Mode,
+    Side,
+    Variant,
+    TimeControl,
+    Puzzle,
+    Start,
+}
+
+impl MenuButton {
+    /// The label of the button, or `None` if it doesn't apply to the mode
+    fn label(self, settings: &GameSettings) -> Option<String> {
+        match self {
+            MenuButton::Mode => Some(format!(
+                "Mode: {}",
+                match settings.mode {
+                    GameMode::HotSeat => "Hot-seat",
+                    GameMode::VsAi => "Computer",
+                    GameMode::VsEngine => "Engine",
+                    GameMode::Puzzle => "Puzzle",
+                    GameMode::Replay => "Replay",
+                }
+            )),
+            MenuButton::Side => match settings.mode {
+                GameMode::VsAi | GameMode::VsEngine => Some(format!(
+                    "Play as {}",
+                    match settings.side {
+                        PieceColor::White => "White",
+                        PieceColor::Black => "Black",
+                    }
+                )),
+                _ => None,
+            },
+            MenuButton::Variant => match settings.mode {
+                GameMode::Puzzle | GameMode::Replay => None,
+                _ => Some(match settings.variants[settings.variant] {
+                    Variant::Standard => "Setup: standard".to_string(),
+                    Variant::Chess960(None) => "Setup: Chess960".to_string(),
+                    Variant::Chess960(Some(index)) => format!("Setup: Chess960 #{}", index),
+                }),
+            },
+            MenuButton::TimeControl => match settings.mode {
+                GameMode::Replay => None,
+                _ => Some(match &settings.time_controls[settings.time_control] {
+                    Some(control) => format!("Time: {}", control),
+                    None => "Time: no clock".to_string(),
+                }),
+            },
+            MenuButton::Puzzle => match settings.mode {
+                GameMode::Puzzle => Some(format!(
+                    "{}/{}: {}",
+                    settings.puzzle + 1,
+                    PUZZLES.len(),
+                    PUZZLES[settings.puzzle].1
+                )),
+                _ => None,
+            },
+            MenuButton::Start => Some("Start".to_string()),
+        }
+    }
+}
+
+fn init_main_menu(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<ScreenMaterials>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.background.clone(),
+            ..Default::default()
+        })
+        .insert(MainMenuScreen)
+        .with_children(|parent| {
+            spawn_text(parent, "Chess!", &font, 60.);
+            for &button in [
+                MenuButton::Mode,
+                MenuButton::Side,
+                MenuButton::Variant,
+                MenuButton::TimeControl,
+                MenuButton::Puzzle,
+                MenuButton::Start,
+            ]
+            .iter()
+            {
+                // The labels are filled in by update_main_menu
+                spawn_button(parent, "", button, &font, &materials);
+            }
+        });
+}
+
+/// Keep the labels in line with the settings, hiding the buttons that don't apply
+fn update_main_menu(
+    settings: Res<GameSettings>,
+    mut button_query: Query<(&MenuButton, &Children, &mut Style)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children, mut style) in button_query.iter_mut() {
+        let label = button.label(&settings);
+        let display = if label.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+        if style.display != display {
+            style.display = display;
+        }
+        if let Some(mut text) = children
+            .first()
+            .and_then(|&child| text_query.get_mut(child).ok())
+        {
+            let label = label.unwrap_or_default();
+            if text.sections[0].value != label {
+                text.sections[0].value = label;
+            }
+        }
+    }
+}
+
+fn menu_buttons(
+    mut commands: Commands,
+    mut mouse_button_inputs: ResMut<Input<MouseButton>>,
+    materials: Res<ScreenMaterials>,
+    mut settings: ResMut<GameSettings>,
+    mut app_state: ResMut<State<AppState>>,
+    mut interaction_query: Query<
+        (&Interaction, &MenuButton, &mut Handle<ColorMaterial>),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, button, mut material) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => match button {
+                MenuButton::Mode => {
+                    let modes = settings.modes();
+                    let i = modes.iter().position(|&mode| mode == settings.mode);
+                    settings.mode = modes[i.map_or(0, |i| (i + 1) % modes.len())];
+                }
+                MenuButton::Side => settings.side = settings.side.other(),
+                MenuButton::Variant => {
+                    settings.variant = (settings.variant + 1) % settings.variants.len();
+                }
+                MenuButton::TimeControl => {
+                    settings.time_control =
+                        (settings.time_control + 1) % settings.time_controls.len();
+                }
+                MenuButton::Puzzle => settings.puzzle = (settings.puzzle + 1) % PUZZLES.len(),
+                MenuButton::Start => {
+                    // Systems check for the replay to know that the game is only looked at
+                    if settings.mode == GameMode::Replay {
+                        commands.insert_resource(Replay::new(settings.pgn_games.clone()));
+                    } else {
+                        commands.remove_resource::<Replay>();
+                    }
+                    // The board may be up this very frame, and shouldn't take the click
+                    mouse_button_inputs.reset(MouseButton::Left);
+                    let _ = app_state.set(AppState::InGame);
+                }
+            },
+            Interaction::Hovered => *material = materials.hovered.clone(),
+            Interaction::None => *material = materials.normal.clone(),
+        }
+    }
+}
+
+/// Sets up a new game from the settings, before its squares and pieces are spawned
+fn start_game(
+    settings: Res<GameSettings>,
+    replay: Option<ResMut<Replay>>,
+    mut game_status: ResMut<GameStatus>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+    mut game_clock: ResMut<GameClock>,
+    mut ai_player: ResMut<AiPlayer>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+) {
+    let game = match replay {
+        Some(mut replay) => {
+            replay.selected = 0;
+            replay.ply = 0;
+            replay.current_game()
+        }
+        None => Game::new(settings.position()),
+    };
+    ai_player.color = settings.ai_color();
+    ai_player.external = settings.mode == GameMode::VsEngine;
+    game_clock.0 = settings
+        .time_control()
+        .map(|control| Clock::new(control.clone(), game.position().side_to_move()));
+    *pending_promotion = PendingPromotion::default();
+    *game_status = GameStatus::new(game);
+    displayed_position.0 = game_status.game.position().clone();
+}
+
+pub struct MenuPlugin;
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<GameSettings>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::MainMenu).with_system(init_main_menu.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(update_main_menu.system())
+                    .with_system(menu_buttons.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::MainMenu)
+                    .with_system(despawn_all::<MainMenuScreen>.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(start_game.system().label("start_game")),
+            );
+    }
+}
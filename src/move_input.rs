@@ -0,0 +1,273 @@
+use bevy::prelude::*;
+
+use crate::{
+    ai::AiPlayer,
+    board::{board_locked, try_move, GameStatus, PendingPromotion, ResetSelectedEvent, StatusType},
+    pieces::DisplayedPosition,
+    replay::Replay,
+    state::{AppState, GameEntity},
+};
+
+/// The move being typed in the box at the bottom left, like `Nf3` or `g1f3`, and why
+/// the last one couldn't be played. Other keyboard shortcuts are off while it's focused
+#[derive(Default)]
+pub struct MoveInput {
+    pub focused: bool,
+    text: String,
+    error: Option<String>,
+}
+
+// Component to mark the box moves are typed in
+struct MoveInputBox;
+
+// Component to mark the Text entity showing the typed move
+struct MoveInputText;
+
+// Component to mark the Text entity explaining why a typed move wasn't played
+struct MoveInputError;
+
+struct MoveInputMaterials {
+    normal: Handle<ColorMaterial>,
+    focused: Handle<ColorMaterial>,
+    transparent: Handle<ColorMaterial>,
+}
+
+impl FromWorld for MoveInputMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        MoveInputMaterials {
+            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            focused: materials.add(Color::rgb(0.25, 0.25, 0.35).into()),
+            transparent: materials.add(Color::NONE.into()),
+        }
+    }
+}
+
+// Longer than any move, in either notation
+const MAX_LENGTH: usize = 10;
+
+fn text_bundle(font: &Handle<Font>) -> TextBundle {
+    TextBundle {
+        text: Text::with_section(
+            "",
+            TextStyle {
+                font: font.clone(),
+                font_size: 20.0,
+                color: Color::rgb(0.8, 0.8, 0.8),
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Initialize the move box in the bottom left corner, above the promotion picker
+fn init_move_input(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<MoveInputMaterials>,
+    replay: Option<Res<Replay>>,
+) {
+    // A replayed game can only be stepped through
+    if replay.is_some() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    bottom: Val::Px(70.),
+                    ..Default::default()
+                },
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.transparent.clone(),
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(ButtonBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(130.), Val::Px(34.)),
+                        align_items: AlignItems::Center,
+                        padding: Rect {
+                            left: Val::Px(8.),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    material: materials.normal.clone(),
+                    ..Default::default()
+                })
+                .insert(MoveInputBox)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(text_bundle(&font))
+                        .insert(MoveInputText);
+                });
+            parent
+                .spawn_bundle(text_bundle(&font))
+                .insert(Style {
+                    margin: Rect {
+                        left: Val::Px(10.),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(MoveInputError);
+        });
+}
+
+/// A new game starts with an empty box
+fn reset_move_input(mut move_input: ResMut<MoveInput>) {
+    *move_input = MoveInput::default();
+}
+
+/// Clicking the box or pressing Tab starts typing a move, and clicking anywhere else
+/// or pressing Escape stops
+fn focus_move_input(
+    mouse_button_inputs: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut move_input: ResMut<MoveInput>,
+    box_query: Query<&Interaction, With<MoveInputBox>>,
+) {
+    let interaction = match box_query.iter().next() {
+        Some(v) => *v,
+        None => return,
+    };
+    let focused = if mouse_button_inputs.just_pressed(MouseButton::Left) {
+        interaction == Interaction::Clicked
+    } else if keyboard_input.just_pressed(KeyCode::Tab) {
+        true
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        false
+    } else {
+        move_input.focused
+    };
+    if focused != move_input.focused {
+        move_input.focused = focused;
+    }
+}
+
+/// Collects what's typed in the focused box, and plays the move on Enter with the
+/// same checks as moving the pieces on the board
+fn type_move(
+    mut received_characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut move_input: ResMut<MoveInput>,
+    mut game_status: ResMut<GameStatus>,
+    mut pending_promotion: ResMut<PendingPromotion>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+    ai_player: Res<AiPlayer>,
+    replay: Option<Res<Replay>>,
+    mut reset_selected_event: EventWriter<ResetSelectedEvent>,
+) {
+    if !move_input.focused {
+        return;
+    }
+
+    for received in received_characters.iter() {
+        let c = received.char;
+        if (c.is_ascii_alphanumeric() || "-=+#".contains(c)) && move_input.text.len() < MAX_LENGTH {
+            move_input.text.push(c);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        move_input.text.pop();
+    }
+    if !keyboard_input.just_pressed(KeyCode::Return) || move_input.text.is_empty() {
+        return;
+    }
+
+    let typed = std::mem::take(&mut move_input.text);
+    let error = if !matches!(game_status.status_type, StatusType::Move) {
+        Some("The game is over".to_string())
+    } else if pending_promotion.is_pending() {
+        Some("Pick the promotion piece first".to_string())
+    } else if board_locked(
+        replay.is_some(),
+        &pending_promotion,
+        &ai_player,
+        &game_status,
+    ) {
+        Some("Wait for your turn".to_string())
+    } else {
+        match game_status.game.position().parse_move(&typed) {
+            Ok(mv) => {
+                if try_move(
+                    mv,
+                    &mut game_status,
+                    &mut pending_promotion,
+                    &mut displayed_position,
+                ) {
+                    reset_selected_event.send(ResetSelectedEvent);
+                    None
+                } else {
+                    Some(format!("{} is not a legal move", typed))
+                }
+            }
+            Err(err) => Some(err.to_string()),
+        }
+    };
+    move_input.error = error;
+}
+
+/// Show the typed move, or a hint while the box is empty, and the last error next to it
+fn update_move_input_text(
+    move_input: Res<MoveInput>,
+    materials: Res<MoveInputMaterials>,
+    mut box_query: Query<&mut Handle<ColorMaterial>, With<MoveInputBox>>,
+    mut text_query: Query<&mut Text, With<MoveInputText>>,
+    mut error_query: Query<&mut Text, (With<MoveInputError>, Without<MoveInputText>)>,
+) {
+    if !move_input.is_changed() {
+        return;
+    }
+    for mut material in box_query.iter_mut() {
+        *material = if move_input.focused {
+            materials.focused.clone()
+        } else {
+            materials.normal.clone()
+        };
+    }
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = if move_input.focused {
+            format!("{}_", move_input.text)
+        } else if move_input.text.is_empty() {
+            "Type a move".to_string()
+        } else {
+            move_input.text.clone()
+        };
+    }
+    if let Some(mut text) = error_query.iter_mut().next() {
+        text.sections[0].value = move_input.error.clone().unwrap_or_default();
+    }
+}
+
+pub struct MoveInputPlugin;
+impl Plugin for MoveInputPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<MoveInput>()
+            .init_resource::<MoveInputMaterials>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(init_move_input.system())
+                    .with_system(reset_move_input.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(focus_move_input.system().label("focus_move_input"))
+                    .with_system(type_move.system().after("focus_move_input")),
+            )
+            .add_system_set(
+                SystemSet::on_in_stack_update(AppState::InGame)
+                    .with_system(update_move_input_text.system()),
+            );
+    }
+}
@@ -0,0 +1,364 @@
+use bevy::{input::mouse::MouseWheel, prelude::*};
+
+use crate::{
+    board::{GameStatus, PendingPromotion},
+    move_log::MoveLog,
+    pieces::{DisplayedPosition, PieceColor},
+    state::{AppState, GameEntity},
+};
+
+/// The half-move whose position is shown while looking back through the game,
+/// or `None` while showing the current position. The board is read-only meanwhile
+#[derive(Default)]
+pub struct Browsing {
+    pub ply: Option<usize>,
+}
+
+// Component to mark the node that clips the move list
+struct MoveListPanel;
+
+// Component to mark the node holding the rows of moves, which moves up and down to scroll
+struct MoveList;
+
+// Component to mark the button of each half-move, with the number of half-moves played after it
+struct MoveButton(usize);
+
+// Component to mark the "back to live" button
+struct LiveButton;
+
+struct MoveListMaterials {
+    panel: Handle<ColorMaterial>,
+    transparent: Handle<ColorMaterial>,
+    normal: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+    current: Handle<ColorMaterial>,
+}
+
+impl FromWorld for MoveListMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        MoveListMaterials {
+            panel: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.8).into()),
+            transparent: materials.add(Color::NONE.into()),
+            normal: materials.add(Color::rgba(0.15, 0.15, 0.15, 0.).into()),
+            hovered: materials.add(Color::rgb(0.35, 0.25, 0.25).into()),
+            current: materials.add(Color::rgb(0.3, 0.6, 0.3).into()),
+        }
+    }
+}
+
+/// How far down the move list is scrolled, in pixels
+#[derive(Default)]
+struct MoveListScroll {
+    offset: f32,
+    // Set when moves are added, so that the last one comes into view
+    to_bottom: bool,
+}
+
+const FONT_SIZE: f32 = 20.;
+const ROW_HEIGHT: f32 = 26.;
+
+fn text_bundle(value: &str, font: &Handle<Font>) -> TextBundle {
+    TextBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size: FONT_SIZE,
+                color: Color::rgb(0.8, 0.8, 0.8),
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Initialize the (empty) move list on the right, and the hidden "back to live" button below it
+fn init_move_list(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<MoveListMaterials>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    top: Val::Px(90.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(190.), Val::Percent(55.)),
+                overflow: Overflow::Hidden,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            material: materials.panel.clone(),
+            ..Default::default()
+        })
+        .insert(MoveListPanel)
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        flex_shrink: 0.,
+                        ..Default::default()
+                    },
+                    material: materials.transparent.clone(),
+                    ..Default::default()
+                })
+                .insert(MoveList);
+        });
+
+    commands
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    bottom: Val::Px(40.),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Px(190.), Val::Px(40.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.panel.clone(),
+            ..Default::default()
+        })
+        .insert(LiveButton)
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent.spawn_bundle(text_bundle("Back to live", &font));
+        });
+}
+
+/// Rebuild the rows of the move list when moves are played or taken back
+fn update_move_list(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<MoveListMaterials>,
+    move_log: Res<MoveLog>,
+    game_status: Res<GameStatus>,
+    mut scroll: ResMut<MoveListScroll>,
+    list_query: Query<(Entity, Option<&Children>), With<MoveList>>,
+) {
+    if !move_log.is_changed() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let start = game_status.game.start_position();
+
+    // When Black moves first, the first row starts with an empty slot
+    let skipped = match start.side_to_move() {
+        PieceColor::White => 0,
+        PieceColor::Black => 1,
+    };
+    let mut rows: Vec<[Option<(usize, &str)>; 2]> = Vec::new();
+    for (i, san) in move_log.san.iter().enumerate() {
+        let slot = i + skipped;
+        if slot % 2 == 0 || rows.is_empty() {
+            rows.push([None, None]);
+        }
+        rows.last_mut().unwrap()[slot % 2] = Some((i + 1, san.as_str()));
+    }
+
+    for (list, children) in list_query.iter() {
+        if let Some(children) = children {
+            for &child in children.iter() {
+                commands.entity(child).despawn_recursive();
+            }
+        }
+        commands.entity(list).with_children(|parent| {
+            for (row, moves) in rows.iter().enumerate() {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Auto, Val::Px(ROW_HEIGHT)),
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: materials.transparent.clone(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        let number = start.fullmove_number() as usize + row;
+                        parent
+                            .spawn_bundle(text_bundle(&format!("{}.", number), &font))
+                            .insert(Style {
+                                size: Size::new(Val::Px(40.), Val::Auto),
+                                margin: Rect {
+                                    left: Val::Px(5.),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            });
+                        for half_move in moves.iter() {
+                            let (ply, san) = half_move.unwrap_or((0, ""));
+                            let mut button = parent.spawn_bundle(ButtonBundle {
+                                style: Style {
+                                    size: Size::new(Val::Px(70.), Val::Px(ROW_HEIGHT - 2.)),
+                                    margin: Rect::all(Val::Px(1.)),
+                                    align_items: AlignItems::Center,
+                                    padding: Rect {
+                                        left: Val::Px(5.),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                                material: materials.normal.clone(),
+                                ..Default::default()
+                            });
+                            button.with_children(|parent| {
+                                parent.spawn_bundle(text_bundle(san, &font));
+                            });
+                            if half_move.is_some() {
+                                button.insert(MoveButton(ply));
+                            }
+                        }
+                    });
+            }
+        });
+    }
+    scroll.to_bottom = true;
+}
+
+/// Scroll the move list with the mouse wheel, keeping it within its rows
+fn scroll_move_list(
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut scroll: ResMut<MoveListScroll>,
+    panel_query: Query<&Node, With<MoveListPanel>>,
+    mut list_query: Query<(&Node, &mut Style), With<MoveList>>,
+) {
+    let panel = match panel_query.iter().next() {
+        Some(v) => v,
+        None => return,
+    };
+    let (list, mut style) = match list_query.iter_mut().next() {
+        Some(v) => v,
+        None => return,
+    };
+
+    for event in mouse_wheel_events.iter() {
+        scroll.offset -= event.y * ROW_HEIGHT;
+    }
+    let max_offset = (list.size.y - panel.size.y).max(0.);
+    if scroll.to_bottom {
+        scroll.offset = max_offset;
+        scroll.to_bottom = false;
+    }
+    scroll.offset = scroll.offset.max(0.).min(max_offset);
+    if style.position.top != Val::Px(-scroll.offset) {
+        style.position.top = Val::Px(-scroll.offset);
+    }
+}
+
+/// Clicking a half-move shows the position after it, and "back to live" the current one
+fn move_list_buttons(
+    pending_promotion: Res<PendingPromotion>,
+    game_status: Res<GameStatus>,
+    mut browsing: ResMut<Browsing>,
+    move_query: Query<(&Interaction, &MoveButton), Changed<Interaction>>,
+    live_query: Query<&Interaction, (Changed<Interaction>, With<LiveButton>)>,
+) {
+    // The pawn waiting to be promoted has to be dealt with first
+    if pending_promotion.is_pending() {
+        return;
+    }
+    for (interaction, button) in move_query.iter() {
+        if *interaction == Interaction::Clicked {
+            let live = button.0 == game_status.game.moves().len();
+            browsing.ply = if live { None } else { Some(button.0) };
+        }
+    }
+    for interaction in live_query.iter() {
+        if *interaction == Interaction::Clicked {
+            browsing.ply = None;
+        }
+    }
+}
+
+/// Highlight the half-move whose position is on the board
+fn color_move_buttons(
+    materials: Res<MoveListMaterials>,
+    browsing: Res<Browsing>,
+    game_status: Res<GameStatus>,
+    mut button_query: Query<(&Interaction, &MoveButton, &mut Handle<ColorMaterial>)>,
+) {
+    let shown = browsing
+        .ply
+        .unwrap_or_else(|| game_status.game.moves().len());
+    for (interaction, button, mut material) in button_query.iter_mut() {
+        let wanted = if button.0 == shown {
+            &materials.current
+        } else if *interaction == Interaction::Hovered {
+            &materials.hovered
+        } else {
+            &materials.normal
+        };
+        if *material != *wanted {
+            *material = wanted.clone();
+        }
+    }
+}
+
+/// Any change to the game, like a move being played, goes back to the current position
+fn leave_browsing(game_status: Res<GameStatus>, mut browsing: ResMut<Browsing>) {
+    if game_status.is_changed() && browsing.ply.is_some() {
+        browsing.ply = None;
+    }
+}
+
+/// Put the browsed position on the board, and show the "back to live" button meanwhile
+fn show_browsed_position(
+    browsing: Res<Browsing>,
+    game_status: Res<GameStatus>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+    mut live_query: Query<&mut Style, With<LiveButton>>,
+) {
+    if !browsing.is_changed() {
+        return;
+    }
+    let positions = game_status.game.positions();
+    let ply = browsing.ply.unwrap_or(positions.len() - 1);
+    if let Some(position) = positions.get(ply) {
+        if &displayed_position.0 != position {
+            displayed_position.0 = position.clone();
+        }
+    }
+    for mut style in live_query.iter_mut() {
+        style.display = if browsing.ply.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+pub struct MoveListPlugin;
+impl Plugin for MoveListPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<Browsing>()
+            .init_resource::<MoveListMaterials>()
+            .init_resource::<MoveListScroll>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame).with_system(init_move_list.system()),
+            )
+            .add_system_set(
+                // The game can still be looked back through once it's over
+                SystemSet::on_in_stack_update(AppState::InGame)
+                    .with_system(update_move_list.system())
+                    .with_system(scroll_move_list.system())
+                    .with_system(move_list_buttons.system().label("move_list_buttons"))
+                    .with_system(color_move_buttons.system())
+                    .with_system(leave_browsing.system().before("move_list_buttons"))
+                    .with_system(show_browsed_position.system().after("move_list_buttons")),
+            );
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    fs,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use bevy::prelude::*;
+use chess_rules::{DrawReason, Move, PgnGame, Position, WinReason};
+
+use crate::{
+    board::{GameStatus, StatusType},
+    clock::GameClock,
+    move_input::MoveInput,
+    replay::Replay,
+    state::AppState,
+};
+
+/// Every move played so far, in Standard Algebraic Notation
+#[derive(Default)]
+pub struct MoveLog {
+    pub san: Vec<String>,
+    // The game the log was written for: where it started and the moves in `san`
+    start: Option<Position>,
+    moves: Vec<Move>,
+}
+
+/// Keep the log in sync with the moves played in the game. Only the moves it shares
+/// with the game the log was written for are kept, so a game that replaces another one
+/// is written anew, however many moves it has
+fn record_moves(game_status: Res<GameStatus>, mut move_log: ResMut<MoveLog>) {
+    if !game_status.is_changed() {
+        return;
+    }
+    let game = &game_status.game;
+    let kept = if move_log.start.as_ref() == Some(game.start_position()) {
+        move_log
+            .moves
+            .iter()
+            .zip(game.moves())
+            .take_while(|(logged, played)| logged == played)
+            .count()
+    } else {
+        0
+    };
+    move_log.start = Some(game.start_position().clone());
+    move_log.moves.truncate(kept);
+    move_log.san.truncate(kept);
+    for i in kept..game.moves().len() {
+        let mv = game.moves()[i];
+        let san = game.positions()[i].to_san(mv);
+        move_log.moves.push(mv);
+        move_log.san.push(san);
+    }
+}
+
+/// Today's date as written in the PGN Date tag, like 2021.04.20
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    // Convert days since the epoch to a civil date, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (seconds / 86400) as i64 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+fn write_pgn(game_status: &GameStatus, game_clock: &GameClock) {
+    let mut pgn = PgnGame::new(game_status.game.clone(), game_status.result());
+    pgn.set_tag("Event", "Casual game");
+    pgn.set_tag("Site", "Bevy Chess");
+    pgn.set_tag("Date", &today());
+    pgn.set_tag("Round", "-");
+    pgn.set_tag("White", "White");
+    pgn.set_tag("Black", "Black");
+    if let Some(clock) = &game_clock.0 {
+        pgn.set_tag("TimeControl", &clock.control().to_string());
+    }
+    if let StatusType::Win(_, WinReason::Timeout)
+    | StatusType::Draw(DrawReason::TimeoutVsInsufficientMaterial) = game_status.status_type
+    {
+        pgn.set_tag("Termination", "time forfeit");
+    }
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let path = format!("game-{}.pgn", seconds);
+    match fs::write(&path, pgn.to_string()) {
+        Ok(()) => println!("Saved the game to {}", path),
+        Err(err) => eprintln!("Could not save the game to {}: {}", path, err),
+    }
+}
+
+/// Saves the game as PGN when P is pressed, and once when it ends
+fn export_pgn(
+    keyboard_input: Res<Input<KeyCode>>,
+    game_status: Res<GameStatus>,
+    game_clock: Res<GameClock>,
+    replay: Option<Res<Replay>>,
+    move_input: Res<MoveInput>,
+    mut saved_game_over: Local<bool>,
+) {
+    // Replayed games come from a file already, so they're only saved on request
+    let is_over = !matches!(game_status.status_type, StatusType::Move) && replay.is_none();
+    let save_pressed = keyboard_input.just_pressed(KeyCode::P) && !move_input.focused;
+    if save_pressed || (is_over && !*saved_game_over) {
+        write_pgn(&game_status, &game_clock);
+    }
+    *saved_game_over = is_over;
+}
+
+pub struct MoveLogPlugin;
+impl Plugin for MoveLogPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<MoveLog>()
+            .add_system(record_moves.system())
+            .add_system_set(
+                // A game that just ended is saved while the game over screen is up
+                SystemSet::on_in_stack_update(AppState::InGame).with_system(export_pgn.system()),
+            );
+    }
+}
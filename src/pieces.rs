@@ -2,190 +2,120 @@ use std::f32::consts::PI;
 
 use self::PieceType::*;
 use bevy::prelude::*;
+use chess_rules::{piece_value, Position, Square};
 
-#[derive(Clone, Copy, PartialEq)]
-pub enum PieceColor {
-    White,
-    Black,
-}
+use crate::{
+    board::GameStatus,
+    state::{AppState, GameEntity},
+};
 
-impl PieceColor {
-    pub fn other(&self) -> Self {
-        match self {
-            PieceColor::Black => PieceColor::White,
-            PieceColor::White => PieceColor::Black,
-        }
-    }
-}
-
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum PieceType {
-    King,
-    Queen,
-    Bishop,
-    Knight,
-    Rook,
-    Pawn,
-}
-
-const LAYOUT: [PieceType; 8] = [Rook, Knight, Bishop, Queen, King, Bishop, Knight, Rook];
+pub use chess_rules::{PieceColor, PieceType};
 
+/// Component for the pieces on the board. The rules live in `chess_rules`, this is
+/// only what's needed to draw them
 #[derive(Clone, Copy, PartialEq)]
 pub struct Piece {
     pub color: PieceColor,
     pub piece_type: PieceType,
-    pub has_moved: bool,
     pub pos: IVec2,
 }
-impl Piece {
-    /// Returns the possible_positions that are available
-    pub fn is_move_valid(&self, target: IVec2, pieces: &[Piece]) -> bool {
-        let mut _color_of_target = None;
-        let mut color_of_target = || match _color_of_target {
-            Some(v) => v,
-            None => {
-                _color_of_target = Some(color_of_square(target, pieces));
-                _color_of_target.unwrap()
-            }
-        };
-        let diff = target - self.pos;
-        let signum = diff.signum();
 
-        match self.piece_type {
-            King => {
-                (diff.abs() <= IVec2::ONE
-                && color_of_target() != Some(self.color))
-                // Castling
-                || !self.has_moved
-                    && diff.x == 0
-                    && diff.y.abs() == 2
-                    && is_path_empty(self.pos, target + signum + if signum.y == -1 {signum} else {IVec2::ZERO}, pieces)
-                    && pieces.iter().any(|&piece| {
-                        piece.piece_type == Rook
-                        && !piece.has_moved
-                        && (piece.pos - self.pos).signum() == signum
-                    })
-            }
-            Queen => {
-                diff == signum * diff.abs().max_element()
-                    && is_path_empty(self.pos, target, pieces)
-                    && color_of_target() != Some(self.color)
-            }
-            Bishop => {
-                diff.x.abs() == diff.y.abs()
-                    && is_path_empty(self.pos, target, pieces)
-                    && color_of_target() != Some(self.color)
-            }
-            Knight => {
-                (diff.abs() == IVec2::new(1, 2) || diff.abs() == IVec2::new(2, 1))
-                    && color_of_target() != Some(self.color)
-            }
-            Rook => {
-                signum.x * signum.y == 0
-                    && is_path_empty(self.pos, target, pieces)
-                    && color_of_target() != Some(self.color)
-            }
-            Pawn => {
-                let direction = match self.color {
-                    PieceColor::White => 1,
-                    PieceColor::Black => -1,
-                };
-                // Normal move
-                (diff.x == direction
-                    && diff.y == 0
-                    && is_path_empty(self.pos, target + signum, pieces))
+/// Converts a board position, where x is the rank and y the file, to a rules square
+pub fn pos_to_square(pos: IVec2) -> Square {
+    Square::new(pos.y as u8, pos.x as u8)
+}
 
-                    // Double move
-                    || (!self.has_moved
-                    && diff.x == direction * 2
-                    && diff.y == 0
-                    && is_path_empty(self.pos, target + signum, pieces))
+pub fn square_to_pos(square: Square) -> IVec2 {
+    IVec2::new(square.rank() as i32, square.file() as i32)
+}
 
-                    // Take piece
-                    || (diff.x == direction
-                        && diff.y.abs() == 1
-                        && color_of_target() == Some(self.color.other()))
-            }
-        }
-    }
+/// The position shown on the board, which the piece entities are kept in sync with.
+/// It's usually the game's current position, but not while replaying a game
+pub struct DisplayedPosition(pub Position);
 
-    pub fn get_pieces_after_move(&self, new_pos: IVec2, pieces: &[Piece]) -> Vec<Self> {
-        pieces
-            .iter()
-            .filter(|p| p.pos != new_pos)
-            .map(|&p| {
-                if p != *self {
-                    p
-                } else {
-                    Piece { pos: new_pos, ..p }
-                }
-            })
-            .collect()
+impl FromWorld for DisplayedPosition {
+    fn from_world(world: &mut World) -> Self {
+        let game_status = world.get_resource::<GameStatus>().unwrap();
+        DisplayedPosition(game_status.game.position().clone())
     }
 }
 
-pub fn is_check_on(pieces: &[Piece], color: PieceColor) -> bool {
-    let ally_king_position = pieces
-        .iter()
-        .find(|p| p.piece_type == PieceType::King && p.color == color)
-        .unwrap()
-        .pos;
-
-    pieces.iter().any(|attacker| {
-        attacker.color != color && attacker.is_move_valid(ally_king_position, pieces)
-    })
+/// The first of the two columns beside the board where the captured pieces of a color go
+pub fn tray_column(color: PieceColor) -> i32 {
+    match color {
+        PieceColor::White => -2,
+        PieceColor::Black => 9,
+    }
 }
 
-pub fn is_check_mate_on(pieces: &[Piece], color: PieceColor) -> bool {
-    for &piece in pieces.iter() {
-        if piece.color != color {
-            continue;
-        }
-        for i in 0..64 {
-            let new_pos = (i % 8, i / 8).into();
-            if !piece.is_move_valid(new_pos, pieces) {
-                continue;
-            }
-            let pieces_after_move: Vec<_> = piece.get_pieces_after_move(new_pos, pieces);
-            if !is_check_on(&pieces_after_move, color) {
-                return false;
-            }
-        }
+// Trays fill away from the board
+fn tray_direction(color: PieceColor) -> i32 {
+    match color {
+        PieceColor::White => -1,
+        PieceColor::Black => 1,
     }
-    true
 }
 
-fn is_path_empty(begin: IVec2, end: IVec2, pieces: &[Piece]) -> bool {
-    let diff = end - begin;
-    let signum = diff.signum();
-    if diff == signum {
-        return true;
-    };
-    let min = (begin + signum).min(end - signum);
-    let max = (begin + signum).max(end - signum);
+/// The pieces missing from the board, compared to the position the game started from, and
+/// where they go in the trays, grouped by type. Pieces beyond those the game started with
+/// must have been promoted pawns
+fn captured_pieces(start: &Position, position: &Position) -> Vec<(IVec2, chess_rules::Piece)> {
+    let mut captured = Vec::new();
+    for &color in [PieceColor::White, PieceColor::Black].iter() {
+        let count = |position: &Position, piece_type| {
+            position
+                .pieces()
+                .filter(|(_, piece)| piece.color == color && piece.piece_type == piece_type)
+                .count()
+        };
+        let mut promoted = 0;
+        let mut missing = Vec::new();
+        for &piece_type in [Knight, Bishop, Rook, Queen].iter() {
+            let at_start = count(start, piece_type);
+            let on_board = count(position, piece_type);
+            promoted += on_board.saturating_sub(at_start);
+            missing.push((piece_type, at_start.saturating_sub(on_board)));
+        }
+        let pawns = count(start, Pawn).saturating_sub(count(position, Pawn) + promoted);
+        missing.insert(0, (Pawn, pawns));
 
-    for piece in pieces {
-        if min <= piece.pos && piece.pos <= max && {
-            let diff = piece.pos - begin;
-            diff == signum * diff.abs().max_element()
-        } {
-            return false;
+        let types = missing
+            .into_iter()
+            .flat_map(|(piece_type, n)| std::iter::repeat(piece_type).take(n));
+        for (slot, piece_type) in types.enumerate() {
+            let pos = IVec2::new(
+                slot as i32 % 8,
+                tray_column(color) + slot as i32 / 8 * tray_direction(color),
+            );
+            captured.push((pos, chess_rules::Piece::new(color, piece_type)));
         }
     }
-    true
+    captured
 }
 
-/// Returns None if square is empty, returns a Some with the color if not
-fn color_of_square(pos: IVec2, pieces: &[Piece]) -> Option<PieceColor> {
-    pieces
-        .iter()
-        .find(|piece| piece.pos == pos)
-        .map(|piece| piece.color)
+/// The material the side is ahead by, in pawns, counting a knight or bishop as 3,
+/// a rook as 5 and a queen as 9. Negative if it's behind
+pub fn material_difference(position: &Position, color: PieceColor) -> i32 {
+    position
+        .pieces()
+        .map(|(_, piece)| {
+            let value = piece_value(piece.piece_type) / 100;
+            if piece.color == color {
+                value
+            } else {
+                -value
+            }
+        })
+        .sum()
 }
 
 const MOVE_SPEED_CONST: f32 = 0.1;
 
-fn move_pieces(time: Res<Time>, mut query: Query<(&mut Transform, &Piece)>) {
+/// Component for the piece being dragged with the mouse, which follows the cursor
+/// instead of sliding to its square
+pub struct Dragged;
+
+fn move_pieces(time: Res<Time>, mut query: Query<(&mut Transform, &Piece), Without<Dragged>>) {
     for (mut transform, piece) in query.iter_mut() {
         // Get the direction to move in
         let direction =
@@ -198,78 +128,216 @@ fn move_pieces(time: Res<Time>, mut query: Query<(&mut Transform, &Piece)>) {
     }
 }
 
+/// Handles to the meshes and materials used to render the pieces
+pub struct PieceMeshes {
+    bishop: Handle<Mesh>,
+    king_body: Handle<Mesh>,
+    king_cross: Handle<Mesh>,
+    knight_base: Handle<Mesh>,
+    knight_head: Handle<Mesh>,
+    pawn: Handle<Mesh>,
+    queen: Handle<Mesh>,
+    rook: Handle<Mesh>,
+    white_material: Handle<StandardMaterial>,
+    black_material: Handle<StandardMaterial>,
+}
+
+impl FromWorld for PieceMeshes {
+    fn from_world(world: &mut World) -> Self {
+        let world = world.cell();
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        let mut materials = world
+            .get_resource_mut::<Assets<StandardMaterial>>()
+            .unwrap();
+        PieceMeshes {
+            // Load all the meshes
+            bishop: asset_server.load("models/chess_kit/pieces.glb#Mesh0/Primitive0"),
+            king_body: asset_server.load("models/chess_kit/pieces.glb#Mesh1/Primitive0"),
+            king_cross: asset_server.load("models/chess_kit/pieces.glb#Mesh2/Primitive0"),
+            knight_base: asset_server.load("models/chess_kit/pieces.glb#Mesh3/Primitive0"),
+            knight_head: asset_server.load("models/chess_kit/pieces.glb#Mesh4/Primitive0"),
+            pawn: asset_server.load("models/chess_kit/pieces.glb#Mesh5/Primitive0"),
+            queen: asset_server.load("models/chess_kit/pieces.glb#Mesh6/Primitive0"),
+            rook: asset_server.load("models/chess_kit/pieces.glb#Mesh7/Primitive0"),
+            // Add some materials
+            white_material: materials.add(Color::rgb(1., 0.8, 0.8).into()),
+            black_material: materials.add(Color::rgb(0.3, 0.3, 0.3).into()),
+        }
+    }
+}
+
+impl PieceMeshes {
+    fn meshes(&self, piece_type: PieceType) -> Vec<Handle<Mesh>> {
+        match piece_type {
+            Rook => vec![self.rook.clone()],
+            Knight => vec![self.knight_base.clone(), self.knight_head.clone()],
+            Bishop => vec![self.bishop.clone()],
+            Queen => vec![self.queen.clone()],
+            King => vec![self.king_body.clone(), self.king_cross.clone()],
+            Pawn => vec![self.pawn.clone()],
+        }
+    }
+
+    fn material(&self, piece_color: PieceColor) -> Handle<StandardMaterial> {
+        match piece_color {
+            PieceColor::White => self.white_material.clone(),
+            PieceColor::Black => self.black_material.clone(),
+        }
+    }
+}
+
+/// Spawns the child meshes that make up a piece of the given type
+fn spawn_piece_meshes(
+    parent: &mut ChildBuilder,
+    piece_meshes: &PieceMeshes,
+    piece_color: PieceColor,
+    piece_type: PieceType,
+) {
+    for mesh in piece_meshes.meshes(piece_type) {
+        parent.spawn_bundle(PbrBundle {
+            mesh,
+            material: piece_meshes.material(piece_color),
+            ..Default::default()
+        });
+    }
+}
+
+fn spawn_piece(
+    commands: &mut Commands,
+    piece_meshes: &PieceMeshes,
+    piece: chess_rules::Piece,
+    pos: IVec2,
+) {
+    commands
+        .spawn_bundle(PbrBundle {
+            transform: Transform {
+                translation: Vec3::new(pos.x as f32, 0., pos.y as f32),
+                scale: Vec3::new(0.2, 0.2, 0.2),
+                rotation: Quat::from_rotation_y(match piece.color {
+                    PieceColor::Black => PI,
+                    PieceColor::White => 0.,
+                }),
+            },
+            ..Default::default()
+        })
+        .insert(Piece {
+            color: piece.color,
+            piece_type: piece.piece_type,
+            pos,
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            spawn_piece_meshes(parent, piece_meshes, piece.color, piece.piece_type);
+        });
+}
+
 fn create_pieces(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    piece_meshes: Res<PieceMeshes>,
+    game_status: Res<GameStatus>,
+    displayed_position: Res<DisplayedPosition>,
 ) {
-    // Load all the meshes
-    let bishop_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh0/Primitive0");
-    let king_body_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh1/Primitive0");
-    let king_cross_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh2/Primitive0");
-    let knight_base_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh3/Primitive0");
-    let knight_head_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh4/Primitive0");
-    let pawn_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh5/Primitive0");
-    let queen_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh6/Primitive0");
-    let rook_handle = asset_server.load("models/chess_kit/pieces.glb#Mesh7/Primitive0");
+    for (square, piece) in displayed_position.0.pieces() {
+        spawn_piece(&mut commands, &piece_meshes, piece, square_to_pos(square));
+    }
+    let start = game_status.game.start_position();
+    for (pos, piece) in captured_pieces(start, &displayed_position.0) {
+        spawn_piece(&mut commands, &piece_meshes, piece, pos);
+    }
+}
 
-    // Add some materials
-    let white_material = materials.add(Color::rgb(1., 0.8, 0.8).into());
-    let black_material = materials.add(Color::rgb(0.3, 0.3, 0.3).into());
+/// Makes the piece entities match the displayed position when it changes. Pieces
+/// that moved slide to their new square, pieces that were taken slide to the trays
+/// beside the board, and promoted pawns have their meshes swapped
+fn sync_pieces(
+    mut commands: Commands,
+    piece_meshes: Res<PieceMeshes>,
+    game_status: Res<GameStatus>,
+    displayed_position: Res<DisplayedPosition>,
+    mut pieces_query: Query<(Entity, &mut Piece, &Children)>,
+) {
+    if !displayed_position.is_changed() {
+        return;
+    }
+
+    // Pieces that are already where they should be stay untouched
+    let mut unmatched: Vec<_> = pieces_query
+        .iter_mut()
+        .map(|(entity, piece, _)| (entity, *piece))
+        .collect();
+    let mut arrivals = Vec::new();
+    let on_board = displayed_position
+        .0
+        .pieces()
+        .map(|(square, piece)| (square_to_pos(square), piece));
+    let captured = captured_pieces(game_status.game.start_position(), &displayed_position.0);
+    for (pos, piece) in on_board.chain(captured) {
+        match unmatched.iter().position(|(_, other)| {
+            other.pos == pos && other.color == piece.color && other.piece_type == piece.piece_type
+        }) {
+            Some(i) => {
+                unmatched.swap_remove(i);
+            }
+            None => arrivals.push((pos, piece)),
+        }
+    }
 
-    let mut spawn_piece = |piece_color, x, y, piece_type| {
-        let meshes = match piece_type {
-            Rook => vec![rook_handle.clone()],
-            Knight => vec![knight_base_handle.clone(), knight_head_handle.clone()],
-            Bishop => vec![bishop_handle.clone()],
-            Queen => vec![queen_handle.clone()],
-            King => vec![king_body_handle.clone(), king_cross_handle.clone()],
-            Pawn => vec![pawn_handle.clone()],
+    for (pos, piece) in arrivals {
+        // Prefer the closest piece of the same type, then a pawn being promoted
+        // (or a promoted piece going back to being a pawn)
+        let distance = |other: &Piece| (other.pos - pos).abs().max_element();
+        let closest = |can_become: &dyn Fn(&Piece) -> bool| {
+            unmatched
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, other))| other.color == piece.color && can_become(other))
+                .min_by_key(|(_, (_, other))| distance(other))
+                .map(|(i, _)| i)
         };
-        commands
-            .spawn_bundle(PbrBundle {
-                transform: Transform {
-                    translation: Vec3::new(x as f32, 0., y as f32),
-                    scale: Vec3::new(0.2, 0.2, 0.2),
-                    rotation: Quat::from_rotation_y(match piece_color {
-                        PieceColor::Black => PI,
-                        PieceColor::White => 0.,
-                    }),
-                },
-                ..Default::default()
-            })
-            .insert(Piece {
-                color: piece_color,
-                piece_type,
-                has_moved: false,
-                pos: (x, y).into(),
-            })
-            .with_children(|parent| {
-                for mesh in meshes {
-                    parent.spawn_bundle(PbrBundle {
-                        mesh: mesh.clone(),
-                        material: match piece_color {
-                            PieceColor::White => white_material.clone(),
-                            PieceColor::Black => black_material.clone(),
-                        },
-                        ..Default::default()
-                    });
+        let found = closest(&|other| other.piece_type == piece.piece_type)
+            .or_else(|| closest(&|other| other.piece_type == Pawn || piece.piece_type == Pawn));
+
+        let (entity, _) = match found {
+            Some(i) => unmatched.swap_remove(i),
+            None => {
+                spawn_piece(&mut commands, &piece_meshes, piece, pos);
+                continue;
+            }
+        };
+        if let Ok((_, mut other, children)) = pieces_query.get_mut(entity) {
+            other.pos = pos;
+            if other.piece_type != piece.piece_type {
+                other.piece_type = piece.piece_type;
+                for &child in children.iter() {
+                    commands.entity(child).despawn_recursive();
                 }
-            });
-    };
+                commands.entity(entity).with_children(|parent| {
+                    spawn_piece_meshes(parent, &piece_meshes, piece.color, piece.piece_type);
+                });
+            }
+        }
+    }
 
-    for (&piece_type, y) in LAYOUT.iter().zip(0..) {
-        spawn_piece(PieceColor::White, 0, y, piece_type);
-        spawn_piece(PieceColor::White, 1, y, Pawn);
-        spawn_piece(PieceColor::Black, 7, y, piece_type);
-        spawn_piece(PieceColor::Black, 6, y, Pawn);
+    // Whatever is left isn't in the position at all, like when a new game starts
+    for (entity, _) in unmatched {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
 pub struct PiecesPlugin;
 impl Plugin for PiecesPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(create_pieces.system())
-            .add_system(move_pieces.system());
+        app.init_resource::<PieceMeshes>()
+            .init_resource::<DisplayedPosition>()
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(create_pieces.system().after("start_game")),
+            )
+            .add_system_set(
+                // Pieces finish sliding to their squares while the game is paused or over
+                SystemSet::on_in_stack_update(AppState::InGame)
+                    .with_system(sync_pieces.system())
+                    .with_system(move_pieces.system()),
+            );
     }
 }
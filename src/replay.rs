@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use chess_rules::{Game, PgnGame};
+
+use crate::{board::GameStatus, pieces::DisplayedPosition, state::AppState};
+
+/// The games of a PGN file being stepped through instead of played
+pub struct Replay {
+    pub games: Vec<PgnGame>,
+    /// Index of the game on the board
+    pub selected: usize,
+    /// Number of half-moves of that game played on the board
+    pub ply: usize,
+}
+impl Replay {
+    pub fn new(games: Vec<PgnGame>) -> Self {
+        Replay {
+            games,
+            selected: 0,
+            ply: 0,
+        }
+    }
+
+    pub fn game(&self) -> &PgnGame {
+        &self.games[self.selected]
+    }
+
+    /// The selected game up to the current half-move
+    pub fn current_game(&self) -> Game {
+        let mut game = self.game().game.clone();
+        game.truncate(self.ply);
+        game
+    }
+}
+
+/// Left/Right step through the moves, Home/End jump to the start/end,
+/// and Up/Down switch between the games of the file
+fn replay_controls(
+    keyboard_input: Res<Input<KeyCode>>,
+    replay: Option<ResMut<Replay>>,
+    mut game_status: ResMut<GameStatus>,
+    mut displayed_position: ResMut<DisplayedPosition>,
+) {
+    let mut replay = match replay {
+        Some(v) => v,
+        None => return,
+    };
+
+    let (mut selected, mut ply) = (replay.selected, replay.ply);
+    if keyboard_input.just_pressed(KeyCode::Up) && selected > 0 {
+        selected -= 1;
+        ply = 0;
+    } else if keyboard_input.just_pressed(KeyCode::Down) && selected + 1 < replay.games.len() {
+        selected += 1;
+        ply = 0;
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        ply = (ply + 1).min(replay.game().game.moves().len());
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        ply = ply.saturating_sub(1);
+    } else if keyboard_input.just_pressed(KeyCode::Home) {
+        ply = 0;
+    } else if keyboard_input.just_pressed(KeyCode::End) {
+        ply = replay.game().game.moves().len();
+    }
+    if (selected, ply) == (replay.selected, replay.ply) {
+        return;
+    }
+
+    replay.selected = selected;
+    replay.ply = ply;
+    *game_status = GameStatus::new(replay.current_game());
+    displayed_position.0 = game_status.game.position().clone();
+}
+
+pub struct ReplayPlugin;
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_system_set(
+            SystemSet::on_update(AppState::InGame).with_system(replay_controls.system()),
+        );
+    }
+}
@@ -0,0 +1,283 @@
+use bevy::{ecs::component::Component, prelude::*};
+
+use crate::{
+    board::{GameStatus, StatusType},
+    menu::GameSettings,
+    move_input::MoveInput,
+    replay::Replay,
+};
+
+/// The screens the app goes through. Paused and GameOver are pushed on top of InGame,
+/// so that the board stays up behind them
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AppState {
+    MainMenu,
+    InGame,
+    Paused,
+    GameOver,
+}
+
+/// Component to mark what's spawned for a game, like the squares, the pieces and the
+/// texts around the board, which all go away when it ends
+pub struct GameEntity;
+
+// Component to mark the root node of the pause screen
+struct PauseScreen;
+
+// Component to mark the root node of the game over screen
+struct GameOverScreen;
+
+/// What the buttons of the pause and game over screens do
+#[derive(Clone, Copy)]
+enum ScreenButton {
+    Resume,
+    Restart,
+    Rematch,
+    MainMenu,
+}
+
+pub struct ScreenMaterials {
+    pub background: Handle<ColorMaterial>,
+    pub normal: Handle<ColorMaterial>,
+    pub hovered: Handle<ColorMaterial>,
+    pub transparent: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ScreenMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ScreenMaterials {
+            background: materials.add(Color::rgba(0.1, 0.1, 0.1, 0.9).into()),
+            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            hovered: materials.add(Color::rgb(0.35, 0.25, 0.25).into()),
+            transparent: materials.add(Color::NONE.into()),
+        }
+    }
+}
+
+/// Spawns a text, like the title of a screen or the label of a button
+pub fn spawn_text(parent: &mut ChildBuilder, value: &str, font: &Handle<Font>, font_size: f32) {
+    parent.spawn_bundle(TextBundle {
+        text: Text::with_section(
+            value,
+            TextStyle {
+                font: font.clone(),
+                font_size,
+                color: Color::rgb(0.8, 0.8, 0.8),
+            },
+            Default::default(),
+        ),
+        ..Default::default()
+    });
+}
+
+/// Spawns a button of a menu screen, with its label and the component telling what it does
+pub fn spawn_button<T: Component>(
+    parent: &mut ChildBuilder,
+    label: &str,
+    action: T,
+    font: &Handle<Font>,
+    materials: &ScreenMaterials,
+) {
+    parent
+        .spawn_bundle(ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(220.), Val::Px(44.)),
+                margin: Rect::all(Val::Px(5.)),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.normal.clone(),
+            ..Default::default()
+        })
+        .insert(action)
+        .with_children(|parent| spawn_text(parent, label, font, 24.));
+}
+
+/// Spawns the panel in the middle of the window holding the title and buttons of a screen
+fn spawn_screen<T: Component>(
+    commands: &mut Commands,
+    marker: T,
+    materials: &ScreenMaterials,
+    spawn_children: impl FnOnce(&mut ChildBuilder),
+) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.), Val::Percent(100.)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            material: materials.transparent.clone(),
+            ..Default::default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        padding: Rect::all(Val::Px(20.)),
+                        ..Default::default()
+                    },
+                    material: materials.background.clone(),
+                    ..Default::default()
+                })
+                .with_children(spawn_children);
+        });
+}
+
+/// Despawns every entity with the component, and their children
+pub fn despawn_all<T: Component>(mut commands: Commands, query: Query<Entity, With<T>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Escape pauses the game, unless it's leaving the move box
+fn pause_game(
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    move_input: Res<MoveInput>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) && !move_input.focused {
+        // The pause screen may come up this very frame, and shouldn't take it as resuming
+        keyboard_input.reset(KeyCode::Escape);
+        let _ = app_state.push(AppState::Paused);
+    }
+}
+
+/// Shows the game over screen once the game has ended. Replayed games are only looked at
+fn detect_game_over(
+    game_status: Res<GameStatus>,
+    replay: Option<Res<Replay>>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if replay.is_none() && !matches!(game_status.status_type, StatusType::Move) {
+        let _ = app_state.push(AppState::GameOver);
+    }
+}
+
+fn init_pause_screen(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<ScreenMaterials>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    spawn_screen(&mut commands, PauseScreen, &materials, |parent| {
+        spawn_text(parent, "Paused", &font, 40.);
+        spawn_button(parent, "Resume", ScreenButton::Resume, &font, &materials);
+        spawn_button(parent, "Restart", ScreenButton::Restart, &font, &materials);
+        spawn_button(
+            parent,
+            "Main menu",
+            ScreenButton::MainMenu,
+            &font,
+            &materials,
+        );
+    });
+}
+
+fn init_game_over_screen(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    materials: Res<ScreenMaterials>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    spawn_screen(&mut commands, GameOverScreen, &materials, |parent| {
+        spawn_text(parent, "Game over", &font, 40.);
+        spawn_button(parent, "Rematch", ScreenButton::Rematch, &font, &materials);
+        spawn_button(
+            parent,
+            "New game",
+            ScreenButton::MainMenu,
+            &font,
+            &materials,
+        );
+    });
+}
+
+/// Escape also resumes the game
+fn resume_game(mut keyboard_input: ResMut<Input<KeyCode>>, mut app_state: ResMut<State<AppState>>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        keyboard_input.reset(KeyCode::Escape);
+        let _ = app_state.pop();
+    }
+}
+
+/// Restarting or leaving replaces the whole stack of states, so that the squares and
+/// pieces of the game are despawned and, for a new game, spawned again
+fn screen_buttons(
+    mut mouse_button_inputs: ResMut<Input<MouseButton>>,
+    materials: Res<ScreenMaterials>,
+    mut settings: ResMut<GameSettings>,
+    mut app_state: ResMut<State<AppState>>,
+    mut interaction_query: Query<
+        (&Interaction, &ScreenButton, &mut Handle<ColorMaterial>),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, button, mut material) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => {
+                // The board may be back this very frame, and shouldn't take the click
+                mouse_button_inputs.reset(MouseButton::Left);
+                let _ = match button {
+                    ScreenButton::Resume => app_state.pop(),
+                    ScreenButton::Restart => app_state.replace(AppState::InGame),
+                    ScreenButton::Rematch => {
+                        // The players swap sides
+                        settings.side = settings.side.other();
+                        app_state.replace(AppState::InGame)
+                    }
+                    ScreenButton::MainMenu => app_state.replace(AppState::MainMenu),
+                };
+            }
+            Interaction::Hovered => *material = materials.hovered.clone(),
+            Interaction::None => *material = materials.normal.clone(),
+        }
+    }
+}
+
+pub struct StatePlugin;
+impl Plugin for StatePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<ScreenMaterials>()
+            .add_state(AppState::MainMenu)
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame)
+                    .with_system(pause_game.system().before("focus_move_input"))
+                    .with_system(detect_game_over.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::InGame)
+                    .with_system(despawn_all::<GameEntity>.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::Paused).with_system(init_pause_screen.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::Paused)
+                    .with_system(resume_game.system())
+                    .with_system(screen_buttons.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::Paused)
+                    .with_system(despawn_all::<PauseScreen>.system()),
+            )
+            .add_system_set(
+                SystemSet::on_enter(AppState::GameOver).with_system(init_game_over_screen.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::GameOver).with_system(screen_buttons.system()),
+            )
+            .add_system_set(
+                SystemSet::on_exit(AppState::GameOver)
+                    .with_system(despawn_all::<GameOverScreen>.system()),
+            );
+    }
+}
@@ -1,14 +1,63 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
+use chess_rules::{DrawReason, UciScore, WinReason};
 
 use crate::{
-    board::{GameStatus, StatusType},
-    pieces::PieceColor,
+    ai::AiAnalysis,
+    board::{GameStatus, PendingPromotion, PromotionEvent, StatusType},
+    book::{BookHint, BookMoves},
+    clock::GameClock,
+    pieces::{material_difference, DisplayedPosition, PieceColor, PieceType},
+    replay::Replay,
+    state::{AppState, GameEntity},
 };
 
 // Component to mark the Text entity
 struct StatusText;
 
-/// Initialize UiCamera and text
+// Component to mark the Text entity showing the clocks
+struct ClockText;
+
+// Component to mark the Text entity showing which side is ahead in material
+struct MaterialText;
+
+// Component to mark the Text entity showing where we are in a replay
+struct ReplayText;
+
+// Component to mark the Text entity showing what the computer is thinking
+struct AnalysisText;
+
+// Component to mark the Text entity showing the opening book's moves
+struct BookText;
+
+// Component to mark the root node of the promotion picker
+struct PromotionPicker;
+
+// Component to mark each button of the promotion picker
+struct PromotionButton(PieceType);
+
+struct ButtonMaterials {
+    normal: Handle<ColorMaterial>,
+    hovered: Handle<ColorMaterial>,
+}
+
+impl FromWorld for ButtonMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.get_resource_mut::<Assets<ColorMaterial>>().unwrap();
+        ButtonMaterials {
+            normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
+            hovered: materials.add(Color::rgb(0.35, 0.25, 0.25).into()),
+        }
+    }
+}
+
+/// Initialize UiCamera, which the menus use too
+fn init_ui_camera(mut commands: Commands) {
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
+/// Initialize the status text
 fn init_next_move_text(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
@@ -18,9 +67,6 @@ fn init_next_move_text(
     let material = color_materials.add(Color::NONE.into());
 
     commands
-        .spawn_bundle(UiCameraBundle::default())
-        // root node
-        .commands()
         .spawn_bundle(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
@@ -34,6 +80,7 @@ fn init_next_move_text(
             material,
             ..Default::default()
         })
+        .insert(GameEntity)
         .with_children(|parent| {
             parent
                 .spawn_bundle(TextBundle {
@@ -57,21 +104,476 @@ fn update_status(game_status: Res<GameStatus>, mut text_query: Query<&mut Text,
     if !game_status.is_changed() {
         return;
     }
-    let color_text = match game_status.color {
+    let color_text = |color: PieceColor| match color {
         PieceColor::White => "White",
         PieceColor::Black => "Black",
     };
     let text_value = match game_status.status_type {
-        StatusType::Win => format!("{} Wins!", color_text),
-        StatusType::Move => format!("Next move: {}", color_text),
+        StatusType::Win(color, WinReason::Checkmate) => format!("{} Wins!", color_text(color)),
+        StatusType::Win(color, WinReason::Timeout) => {
+            format!("{} Wins on time!", color_text(color))
+        }
+        StatusType::Win(color, WinReason::Resignation) => {
+            format!("{} Wins by resignation!", color_text(color))
+        }
+        // Repeated positions are counted, so that players know when they can claim a draw
+        StatusType::Move => match game_status.game.repetitions() {
+            1 => format!("Next move: {}", color_text(game_status.color)),
+            times => format!(
+                "Next move: {} (seen {} times)",
+                color_text(game_status.color),
+                times
+            ),
+        },
+        StatusType::Draw(reason) => format!(
+            "Draw by {}",
+            match reason {
+                DrawReason::Stalemate => "stalemate",
+                DrawReason::InsufficientMaterial => "insufficient material",
+                DrawReason::ThreefoldRepetition => "threefold repetition",
+                DrawReason::FiftyMoveRule => "the fifty-move rule",
+                DrawReason::FivefoldRepetition => "fivefold repetition",
+                DrawReason::SeventyFiveMoveRule => "the seventy-five-move rule",
+                DrawReason::Agreement => "agreement",
+                DrawReason::TimeoutVsInsufficientMaterial => {
+                    "timeout vs insufficient material"
+                }
+            }
+        ),
+    };
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = text_value;
+    }
+}
+
+/// Initialize the clocks in the top right corner, across from the status
+fn init_clock_text(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    game_clock: Res<GameClock>,
+) {
+    if game_clock.0.is_none() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let material = color_materials.add(Color::NONE.into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    top: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 30.0,
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(ClockText);
+        });
+}
+
+/// Formats the time left like 4:05, 1:02:03, or 9.7 in the last ten seconds
+fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else if seconds >= 10 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("{:.1}", time.as_secs_f32())
+    }
+}
+
+/// Show the time left for both sides, marking the one running
+fn update_clock_text(
+    game_clock: Res<GameClock>,
+    mut text_query: Query<&mut Text, With<ClockText>>,
+) {
+    let clock = match &game_clock.0 {
+        Some(v) if game_clock.is_changed() => v,
+        _ => return,
+    };
+    let line = |color: PieceColor, name: &str| {
+        let marker = if clock.turn() == color { "> " } else { "" };
+        format!("{}{} {}", marker, name, format_time(clock.remaining(color)))
+    };
+    let text_value = format!(
+        "{}\n{}",
+        line(PieceColor::White, "White"),
+        line(PieceColor::Black, "Black")
+    );
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = text_value;
+    }
+}
+
+/// Initialize the replay text, below the status, when replaying a PGN file
+fn init_replay_text(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    replay: Option<Res<Replay>>,
+) {
+    if replay.is_none() {
+        return;
+    }
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let material = color_materials.add(Color::NONE.into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    top: Val::Px(85.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(ReplayText);
+        });
+}
+
+/// Initialize the material balance text below the status
+fn init_material_text(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let material = color_materials.add(Color::NONE.into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    top: Val::Px(55.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        "",
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 20.0,
+                            color: Color::rgb(0.8, 0.8, 0.8),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(MaterialText);
+        });
+}
+
+/// Show how much material the side ahead has over the other, for the position on the board
+fn update_material_text(
+    displayed_position: Res<DisplayedPosition>,
+    mut text_query: Query<&mut Text, With<MaterialText>>,
+) {
+    if !displayed_position.is_changed() {
+        return;
+    }
+    let difference = material_difference(&displayed_position.0, PieceColor::White);
+    let text_value = match difference {
+        0 => String::new(),
+        d if d > 0 => format!("White +{}", d),
+        d => format!("Black +{}", -d),
     };
     if let Some(mut text) = text_query.iter_mut().next() {
         text.sections[0].value = text_value;
     }
 }
 
-/// Demo system to show off Query transformers
-fn log_text_changes(query: Query<&Text, Changed<Text>>) {
+/// Show the replayed game and move
+fn update_replay_text(
+    replay: Option<Res<Replay>>,
+    mut text_query: Query<&mut Text, With<ReplayText>>,
+) {
+    let replay = match replay {
+        Some(v) if v.is_changed() => v,
+        _ => return,
+    };
+    let pgn_game = replay.game();
+    let tag = |name| pgn_game.tag(name).unwrap_or("?");
+    let text_value = format!(
+        "Game {}/{}: {} - {} ({})\nMove {}/{}",
+        replay.selected + 1,
+        replay.games.len(),
+        tag("White"),
+        tag("Black"),
+        pgn_game.result.as_str(),
+        replay.ply,
+        pgn_game.game.moves().len(),
+    );
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = text_value;
+    }
+}
+
+/// Initialize the analysis text in the bottom right corner, with the book's moves above it
+fn init_analysis_text(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let material = color_materials.add(Color::NONE.into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    right: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                align_items: AlignItems::FlexEnd,
+                ..Default::default()
+            },
+            material,
+            ..Default::default()
+        })
+        .insert(GameEntity)
+        .with_children(|parent| {
+            let text_bundle = || TextBundle {
+                text: Text::with_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: Color::rgb(0.8, 0.8, 0.8),
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            };
+            parent.spawn_bundle(text_bundle()).insert(BookText);
+            parent.spawn_bundle(text_bundle()).insert(AnalysisText);
+        });
+}
+
+/// Show the depth, score and best line of the computer's last search
+fn update_analysis_text(
+    analysis: Res<AiAnalysis>,
+    mut text_query: Query<&mut Text, With<AnalysisText>>,
+) {
+    if !analysis.is_changed() {
+        return;
+    }
+    let info = match &analysis.0 {
+        Some(v) => v,
+        None => return,
+    };
+    let score = match info.score {
+        Some(UciScore::Centipawns(score)) => format!("{:+.2}", score as f32 / 100.),
+        Some(UciScore::Mate(moves)) if moves > 0 => format!("mate in {}", moves),
+        Some(UciScore::Mate(moves)) => format!("mated in {}", -moves),
+        None => "?".to_string(),
+    };
+    let line: Vec<_> = info.pv.iter().take(5).map(|mv| mv.to_string()).collect();
+    let text_value = format!(
+        "Depth {}: {} {}",
+        info.depth.unwrap_or(0),
+        score,
+        line.join(" ")
+    );
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = text_value;
+    }
+}
+
+/// Show whether the game is still in the opening book, with the book's moves and their weights,
+/// heaviest first, and the move picked when the player asked for a hint
+fn update_book_text(
+    book_moves: Res<BookMoves>,
+    hint: Res<BookHint>,
+    game_status: Res<GameStatus>,
+    mut text_query: Query<&mut Text, With<BookText>>,
+) {
+    if !book_moves.is_changed() && !hint.is_changed() {
+        return;
+    }
+    let position = game_status.game.position();
+    let mut moves = book_moves.0.clone();
+    moves.sort_by_key(|&(_, weight)| std::cmp::Reverse(weight));
+    let listed: Vec<_> = moves
+        .iter()
+        .take(5)
+        .map(|&(mv, weight)| format!("{} ({})", position.to_san(mv), weight))
+        .collect();
+    let mut text_value = match listed.len() {
+        0 => String::new(),
+        _ => format!("In book: {}", listed.join(", ")),
+    };
+    if let Some(mv) = hint.0 {
+        text_value.push_str(&format!("\nBook move: {}", position.to_san(mv)));
+    }
+    if let Some(mut text) = text_query.iter_mut().next() {
+        text.sections[0].value = text_value;
+    }
+}
+
+/// Initialize the (hidden) promotion picker
+fn init_promotion_picker(
+    mut commands: Commands,
+    asset_server: ResMut<AssetServer>,
+    button_materials: Res<ButtonMaterials>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let font = asset_server.load("fonts/FiraSans-Bold.ttf");
+    let material = color_materials.add(Color::NONE.into());
+
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.),
+                    bottom: Val::Px(10.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            material,
+            ..Default::default()
+        })
+        .insert(PromotionPicker)
+        .insert(GameEntity)
+        .with_children(|parent| {
+            for (piece_type, label) in [
+                (PieceType::Queen, "Queen"),
+                (PieceType::Rook, "Rook"),
+                (PieceType::Bishop, "Bishop"),
+                (PieceType::Knight, "Knight"),
+            ]
+            .iter()
+            {
+                parent
+                    .spawn_bundle(ButtonBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(130.), Val::Px(50.)),
+                            margin: Rect::all(Val::Px(5.)),
+                            justify_content: JustifyContent::Center,
+                            align_items: AlignItems::Center,
+                            ..Default::default()
+                        },
+                        material: button_materials.normal.clone(),
+                        ..Default::default()
+                    })
+                    .insert(PromotionButton(*piece_type))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                *label,
+                                TextStyle {
+                                    font: font.clone(),
+                                    font_size: 30.0,
+                                    color: Color::rgb(0.8, 0.8, 0.8),
+                                },
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        });
+                    });
+            }
+        });
+}
+
+/// Only show the promotion picker while a pawn is waiting to be promoted
+fn show_promotion_picker(
+    pending_promotion: Res<PendingPromotion>,
+    mut picker_query: Query<&mut Style, With<PromotionPicker>>,
+) {
+    if !pending_promotion.is_changed() {
+        return;
+    }
+    for mut style in picker_query.iter_mut() {
+        style.display = if pending_promotion.is_pending() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+fn promotion_buttons(
+    button_materials: Res<ButtonMaterials>,
+    mut promotion_event: EventWriter<PromotionEvent>,
+    mut interaction_query: Query<
+        (&Interaction, &PromotionButton, &mut Handle<ColorMaterial>),
+        Changed<Interaction>,
+    >,
+) {
+    for (interaction, button, mut material) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Clicked => promotion_event.send(PromotionEvent(button.0)),
+            Interaction::Hovered => *material = button_materials.hovered.clone(),
+            Interaction::None => *material = button_materials.normal.clone(),
+        }
+    }
+}
+
+/// Demo system to show off Query transformers. Only logs the status, since the clocks
+/// change every frame
+fn log_text_changes(query: Query<&Text, (Changed<Text>, With<StatusText>)>) {
     for text in query.iter() {
         println!("New text: {}", text.sections[0].value);
     }
@@ -80,8 +582,31 @@ fn log_text_changes(query: Query<&Text, Changed<Text>>) {
 pub struct UIPlugin;
 impl Plugin for UIPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_startup_system(init_next_move_text.system())
-            .add_system(update_status.system())
-            .add_system(log_text_changes.system());
+        app.init_resource::<ButtonMaterials>()
+            .add_startup_system(init_ui_camera.system())
+            .add_system_set(
+                SystemSet::on_enter(AppState::InGame)
+                    .with_system(init_next_move_text.system())
+                    .with_system(init_promotion_picker.system())
+                    .with_system(init_material_text.system())
+                    .with_system(init_replay_text.system())
+                    .with_system(init_analysis_text.system())
+                    .with_system(init_clock_text.system().after("start_game")),
+            )
+            .add_system_set(
+                // The texts stay up to date while the game is paused or over
+                SystemSet::on_in_stack_update(AppState::InGame)
+                    .with_system(update_status.system())
+                    .with_system(update_material_text.system())
+                    .with_system(update_replay_text.system())
+                    .with_system(update_analysis_text.system())
+                    .with_system(update_book_text.system())
+                    .with_system(update_clock_text.system())
+                    .with_system(show_promotion_picker.system())
+                    .with_system(log_text_changes.system()),
+            )
+            .add_system_set(
+                SystemSet::on_update(AppState::InGame).with_system(promotion_buttons.system()),
+            );
     }
 }